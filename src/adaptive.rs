@@ -0,0 +1,126 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use fastrace::prelude::TraceId;
+
+/// The period over which the throughput of bridged spans is measured.
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// The default lower bound of the ratio, see [`AdaptiveSampler::with_min_ratio`].
+const DEFAULT_MIN_RATIO: f64 = 0.001;
+
+/// A sampler lowering the ratio of traces bridged when the throughput of bridged spans exceeds a
+/// ceiling, and raising it again when the load drops.
+///
+/// The sampler is installed with
+/// [`FastraceCompatLayer::with_adaptive_sampling`](crate::FastraceCompatLayer::with_adaptive_sampling).
+/// It is a cheap handle, so a clone can be kept to monitor the current ratio.
+///
+/// The throughput is measured every second, and the ratio is set so that the throughput
+/// estimated without sampling, scaled by the ratio, meets the ceiling. Sampling decisions are
+/// taken when the layer starts a new trace and derived from its trace id, so that traces are
+/// either bridged in full or not at all. Only the spans of such traces are measured: spans
+/// attached to a fastrace parent set by the application are not sampled, so lowering the ratio
+/// would not reduce them. The ratio never falls below a minimum, see
+/// [`AdaptiveSampler::with_min_ratio`], and returns to 1 once the throughput drops under the
+/// ceiling.
+///
+/// # Example
+///
+/// ```
+/// use fastrace_tracing::AdaptiveSampler;
+/// use fastrace_tracing::FastraceCompatLayer;
+///
+/// let sampler = AdaptiveSampler::new(10_000);
+/// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+///     .with_adaptive_sampling(sampler.clone());
+///
+/// assert_eq!(sampler.ratio(), 1.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdaptiveSampler {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    max_spans_per_second: f64,
+    min_ratio: f64,
+    ratio_bits: AtomicU64,
+    spans: AtomicU64,
+    window_start: Mutex<Instant>,
+}
+
+impl AdaptiveSampler {
+    /// Creates an [`AdaptiveSampler`] keeping the throughput of bridged spans under
+    /// `max_spans_per_second`.
+    pub fn new(max_spans_per_second: u64) -> Self {
+        Self::with_bounds(max_spans_per_second as f64, DEFAULT_MIN_RATIO)
+    }
+
+    /// Sets the lower bound of the ratio, so that some traces are still bridged under extreme
+    /// load. `min_ratio` is clamped to `(0, 1]`.
+    ///
+    /// Default is `0.001`.
+    pub fn with_min_ratio(self, min_ratio: f64) -> Self {
+        Self::with_bounds(self.inner.max_spans_per_second, min_ratio)
+    }
+
+    fn with_bounds(max_spans_per_second: f64, min_ratio: f64) -> Self {
+        AdaptiveSampler {
+            inner: Arc::new(Inner {
+                max_spans_per_second,
+                min_ratio: min_ratio.clamp(f64::MIN_POSITIVE, 1.0),
+                ratio_bits: AtomicU64::new(1.0f64.to_bits()),
+                spans: AtomicU64::new(0),
+                window_start: Mutex::new(Instant::now()),
+            }),
+        }
+    }
+
+    /// Returns the current ratio of new traces that are bridged, between the minimum ratio and 1.
+    pub fn ratio(&self) -> f64 {
+        f64::from_bits(self.inner.ratio_bits.load(Ordering::Relaxed))
+    }
+
+    /// Returns `true` if the trace with the given id should be bridged.
+    pub(crate) fn sample(&self, trace_id: TraceId) -> bool {
+        self.adjust();
+        let ratio = self.ratio();
+        ratio >= 1.0 || crate::trace_ratio(trace_id) < ratio
+    }
+
+    /// Counts a bridged span of a sampled trace.
+    pub(crate) fn record_span(&self) {
+        self.inner.spans.fetch_add(1, Ordering::Relaxed);
+        self.adjust();
+    }
+
+    /// Adjusts the ratio at the end of each measurement window.
+    fn adjust(&self) {
+        let Ok(mut window_start) = self.inner.window_start.try_lock() else {
+            return;
+        };
+        let elapsed = window_start.elapsed();
+        if elapsed < WINDOW {
+            return;
+        }
+        *window_start = Instant::now();
+
+        let spans = self.inner.spans.swap(0, Ordering::Relaxed) as f64;
+        let unsampled_per_second = spans / elapsed.as_secs_f64() / self.ratio();
+        let ratio = if unsampled_per_second > self.inner.max_spans_per_second {
+            self.inner.max_spans_per_second / unsampled_per_second
+        } else {
+            1.0
+        };
+        let ratio = ratio.clamp(self.inner.min_ratio, 1.0);
+        self.inner
+            .ratio_bits
+            .store(ratio.to_bits(), Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::Duration;
+use std::time::Instant;
+
+/// Marks a tracing span that is aggregated into its nearest recorded ancestor rather than being
+/// recorded on its own.
+pub(crate) struct AggregatedSpan {
+    start: Instant,
+}
+
+impl AggregatedSpan {
+    pub(crate) fn new() -> Self {
+        AggregatedSpan {
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// The durations of the aggregated child spans of a recorded span, grouped by name.
+#[derive(Default)]
+pub(crate) struct SpanAggregates {
+    spans: Vec<(&'static str, Vec<Duration>)>,
+}
+
+impl SpanAggregates {
+    pub(crate) fn push(&mut self, name: &'static str, duration: Duration) {
+        match self.spans.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, durations)) => durations.push(duration),
+            None => self.spans.push((name, vec![duration])),
+        }
+    }
+
+    /// Records one representative child span of `parent` per aggregated span name.
+    pub(crate) fn emit(self, parent: &fastrace::Span) {
+        for (name, mut durations) in self.spans {
+            durations.sort_unstable();
+            let count = durations.len();
+            let min = durations[0];
+            let max = durations[count - 1];
+            let p50 = durations[(count - 1) / 2];
+
+            let _span = fastrace::Span::enter_with_parent(name, parent).with_properties(|| {
+                [
+                    ("repeat.count", count.to_string()),
+                    ("duration.min_ns", min.as_nanos().to_string()),
+                    ("duration.max_ns", max.as_nanos().to_string()),
+                    ("duration.p50_ns", p50.as_nanos().to_string()),
+                ]
+            });
+        }
+    }
+}
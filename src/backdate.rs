@@ -0,0 +1,65 @@
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use fastrace::collector::Reporter;
+use fastrace::collector::SpanRecord;
+
+use crate::Property;
+
+/// The event property holding the original time of an event, in nanoseconds since the Unix
+/// epoch.
+pub(crate) const PROPERTY_EVENT_TIME: &str = "event.time_unix_ns";
+
+/// Returns the `event.time_unix_ns` property of an event happening now, for events that are
+/// added to their span later.
+pub(crate) fn event_time_now() -> Property {
+    let time_unix_ns = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    (PROPERTY_EVENT_TIME.into(), time_unix_ns.to_string().into())
+}
+
+/// A [`Reporter`] that moves events to their original time before reporting them.
+///
+/// fastrace timestamps an event when it is added to its span. Events bridged from a tracing
+/// event carrying a `timestamp` or `event.time_unix_nano` field, e.g., when replaying buffered
+/// records, keep their original time in an `event.time_unix_ns` property instead. This reporter
+/// uses that property as the event timestamp, so that such events land at their original
+/// position on the timeline.
+///
+/// # Example
+///
+/// ```
+/// use fastrace::collector::Config;
+/// use fastrace::collector::ConsoleReporter;
+/// use fastrace_tracing::BackdatingReporter;
+///
+/// fastrace::set_reporter(BackdatingReporter::new(ConsoleReporter), Config::default());
+/// ```
+pub struct BackdatingReporter<R> {
+    inner: R,
+}
+
+impl<R> BackdatingReporter<R> {
+    /// Creates a [`BackdatingReporter`] reporting to `inner`.
+    pub fn new(inner: R) -> Self {
+        BackdatingReporter { inner }
+    }
+}
+
+impl<R: Reporter> Reporter for BackdatingReporter<R> {
+    fn report(&mut self, mut spans: Vec<SpanRecord>) {
+        for event in spans.iter_mut().flat_map(|span| span.events.iter_mut()) {
+            let time = event
+                .properties
+                .iter()
+                .find(|(key, _)| key == PROPERTY_EVENT_TIME)
+                .and_then(|(_, value)| value.parse().ok());
+            if let Some(time) = time {
+                event.timestamp_unix_ns = time;
+            }
+        }
+        self.inner.report(spans);
+    }
+}
@@ -0,0 +1,203 @@
+use std::borrow::Cow;
+
+use crate::with_span_extensions;
+
+/// The field name prefix marking a span field as baggage.
+pub(crate) const FIELD_BAGGAGE_PREFIX: &str = "baggage.";
+
+/// A set of key-value pairs that are propagated as properties to all descendant spans bridged
+/// by the [`FastraceCompatLayer`](crate::FastraceCompatLayer).
+///
+/// Baggage is attached to a tracing span either with [`set_baggage`] or by recording a field
+/// prefixed with `baggage.` (e.g., `baggage.tenant_id = "acme"`). Every bridged span created
+/// within that span then carries a `tenant_id` property without having to copy it manually.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Baggage {
+    entries: Vec<(String, String)>,
+}
+
+impl Baggage {
+    /// Creates an empty [`Baggage`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts an entry, replacing the previous value of `key` if present.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        let key = key.into();
+        let value = value.into();
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+
+    /// Returns the value of `key`, if present.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns an iterator over the entries, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Decodes a W3C `baggage` header value.
+    ///
+    /// Entries that are malformed are skipped, and entry metadata (the `;`-separated properties
+    /// following a value) is discarded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::Baggage;
+    ///
+    /// let baggage = Baggage::decode_w3c_baggage("tenant_id=acme, user_id=42;sensitive");
+    /// assert_eq!(baggage.get("tenant_id"), Some("acme"));
+    /// assert_eq!(baggage.get("user_id"), Some("42"));
+    /// ```
+    pub fn decode_w3c_baggage(header: &str) -> Self {
+        let mut baggage = Baggage::new();
+        for member in header.split(',') {
+            let entry = member.split(';').next().unwrap_or_default();
+            let Some((key, value)) = entry.split_once('=') else {
+                continue;
+            };
+            let key = key.trim();
+            if key.is_empty() {
+                continue;
+            }
+            baggage.insert(key, percent_decode(value.trim()));
+        }
+        baggage
+    }
+
+    /// Encodes the entries as a W3C `baggage` header value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::Baggage;
+    ///
+    /// let mut baggage = Baggage::new();
+    /// baggage.insert("tenant_id", "acme");
+    /// baggage.insert("region", "eu west");
+    /// assert_eq!(
+    ///     baggage.encode_w3c_baggage(),
+    ///     "tenant_id=acme,region=eu%20west"
+    /// );
+    /// ```
+    pub fn encode_w3c_baggage(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, percent_encode(v)))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Merges the entries of `other` into `self`, overriding existing keys.
+    pub(crate) fn extend(&mut self, other: Baggage) {
+        for (key, value) in other.entries {
+            self.insert(key, value);
+        }
+    }
+
+    /// Returns the entries as fastrace properties.
+    pub(crate) fn properties(
+        &self,
+    ) -> impl Iterator<Item = (Cow<'static, str>, Cow<'static, str>)> + '_ {
+        self.entries
+            .iter()
+            .map(|(k, v)| (Cow::Owned(k.clone()), Cow::Owned(v.clone())))
+    }
+}
+
+/// Returns the baggage attached to `span`, including the baggage inherited from its ancestors.
+///
+/// This is typically used to inject the baggage into outgoing requests with
+/// [`Baggage::encode_w3c_baggage`].
+pub fn get_baggage(span: &tracing::Span) -> Baggage {
+    with_span_extensions(span, |extensions| extensions.get_mut::<Baggage>().cloned())
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Attaches a baggage entry to `span`.
+///
+/// The entry is recorded as a property on the fastrace span bridged from `span`, and on every
+/// bridged span created within `span` from now on. This has no effect if `span` is disabled or
+/// the [`FastraceCompatLayer`](crate::FastraceCompatLayer) is not installed.
+///
+/// # Example
+///
+/// ```
+/// let span = tracing::info_span!("request");
+/// fastrace_tracing::set_baggage(&span, "tenant_id", "acme");
+/// ```
+pub fn set_baggage(span: &tracing::Span, key: impl Into<String>, value: impl Into<String>) {
+    let key = key.into();
+    let value = value.into();
+    with_span_extensions(span, |extensions| {
+        if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+            fastrace_span.add_property(|| (key.clone(), value.clone()));
+        }
+        match extensions.get_mut::<Baggage>() {
+            Some(baggage) => baggage.insert(key, value),
+            None => {
+                let mut baggage = Baggage::new();
+                baggage.insert(key, value);
+                extensions.insert(baggage);
+            }
+        }
+    });
+}
+
+/// Percent-encodes the characters that are not allowed in a W3C baggage value.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            // baggage-octet, excluding `%` to keep the encoding reversible.
+            0x21 | 0x23..=0x24 | 0x26..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Decodes percent-encoded octets in a W3C baggage value.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok());
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
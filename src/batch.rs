@@ -0,0 +1,167 @@
+use std::cell::RefCell;
+use std::mem;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use tracing_core::span;
+
+static NEXT_BATCHER_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// The batches of the current thread, one per [`EventBatcher`].
+    static THREAD_BATCHES: RefCell<Vec<(u64, Arc<Mutex<ThreadBatch>>)>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// The events a thread has buffered for a span.
+struct ThreadBatch {
+    span: Option<span::Id>,
+    events: Vec<fastrace::Event>,
+    since: Instant,
+}
+
+/// Events of a span that are due to be added to it.
+pub(crate) type DueEvents = (span::Id, Vec<fastrace::Event>);
+
+/// Buffers events per thread, so that they are added to their span in batches rather than one
+/// by one, each time taking the lock on the registry slot of the span.
+///
+/// Each thread buffers the events of one span at a time. The batch is handed back once it is
+/// full or too old, or when the thread records an event on another span. The batches of other
+/// threads are checked for stale events at most once per `max_delay`, by whichever thread pushes
+/// an event next. The batches of all threads, including exited ones, are drained when their span
+/// closes.
+pub(crate) struct EventBatcher {
+    id: u64,
+    max_events: usize,
+    max_delay: Duration,
+    batches: Mutex<Vec<Arc<Mutex<ThreadBatch>>>>,
+    created: Instant,
+    /// When the batches of all threads are checked next, in nanoseconds since `created`.
+    next_sweep: AtomicU64,
+}
+
+impl EventBatcher {
+    pub(crate) fn new(max_events: usize, max_delay: Duration) -> Self {
+        EventBatcher {
+            id: NEXT_BATCHER_ID.fetch_add(1, Ordering::Relaxed),
+            max_events,
+            max_delay,
+            batches: Mutex::new(Vec::new()),
+            created: Instant::now(),
+            next_sweep: AtomicU64::new(max_delay.as_nanos() as u64),
+        }
+    }
+
+    /// Buffers `event` of the span `id` on the current thread.
+    ///
+    /// Returns the events that are due to be added to their span.
+    pub(crate) fn push(&self, id: &span::Id, event: fastrace::Event) -> Vec<DueEvents> {
+        let Some(batch) = self.thread_batch() else {
+            // The thread is exiting, so there is nothing to buffer the event in.
+            return vec![(id.clone(), vec![event])];
+        };
+        let mut batch = batch.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut due = Vec::new();
+        if batch.span.as_ref() != Some(id) {
+            if let Some(span) = batch.span.replace(id.clone()) {
+                if !batch.events.is_empty() {
+                    due.push((span, mem::take(&mut batch.events)));
+                }
+            }
+        }
+        if batch.events.is_empty() {
+            batch.since = Instant::now();
+        }
+        batch.events.push(event);
+
+        if batch.events.len() >= self.max_events || batch.since.elapsed() >= self.max_delay {
+            due.push((id.clone(), mem::take(&mut batch.events)));
+        }
+        drop(batch);
+
+        if self.sweep_due() {
+            self.take_stale(&mut due);
+        }
+        due
+    }
+
+    /// Returns whether the batches of all threads are due to be checked for stale events,
+    /// letting only one thread check them per `max_delay`.
+    fn sweep_due(&self) -> bool {
+        let now = self.created.elapsed().as_nanos() as u64;
+        let next = self.next_sweep.load(Ordering::Relaxed);
+        now >= next
+            && self
+                .next_sweep
+                .compare_exchange(
+                    next,
+                    now.saturating_add(self.max_delay.as_nanos() as u64),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    /// Takes the batches of all threads that are older than `max_delay`.
+    ///
+    /// Batches locked by their thread are skipped, as that thread is handling them.
+    fn take_stale(&self, due: &mut Vec<DueEvents>) {
+        let batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        for batch in batches.iter() {
+            let Ok(mut batch) = batch.try_lock() else {
+                continue;
+            };
+            if batch.events.is_empty() || batch.since.elapsed() < self.max_delay {
+                continue;
+            }
+            if let Some(span) = batch.span.clone() {
+                due.push((span, mem::take(&mut batch.events)));
+            }
+        }
+    }
+
+    /// Takes the events buffered for the span `id` by all threads.
+    pub(crate) fn take(&self, id: &span::Id) -> Vec<fastrace::Event> {
+        let mut events = Vec::new();
+        let mut batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        batches.retain(|batch| {
+            let mut batch_guard = batch.lock().unwrap_or_else(|e| e.into_inner());
+            if batch_guard.span.as_ref() == Some(id) {
+                batch_guard.span = None;
+                events.append(&mut batch_guard.events);
+            }
+            // Forget the batches of exited threads once they are drained.
+            Arc::strong_count(batch) > 1 || !batch_guard.events.is_empty()
+        });
+        events
+    }
+
+    fn thread_batch(&self) -> Option<Arc<Mutex<ThreadBatch>>> {
+        THREAD_BATCHES
+            .try_with(|thread_batches| {
+                let mut thread_batches = thread_batches.borrow_mut();
+                if let Some((_, batch)) = thread_batches.iter().find(|(id, _)| *id == self.id) {
+                    return batch.clone();
+                }
+
+                let batch = Arc::new(Mutex::new(ThreadBatch {
+                    span: None,
+                    events: Vec::new(),
+                    since: Instant::now(),
+                }));
+                self.batches
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(batch.clone());
+                thread_batches.push((self.id, batch.clone()));
+                batch
+            })
+            .ok()
+    }
+}
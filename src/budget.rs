@@ -0,0 +1,173 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+use crate::backdate::event_time_now;
+use crate::Property;
+
+/// Describes what the layer does once a bridged span exceeds its memory budget, configured with
+/// [`FastraceCompatLayer::with_span_memory_budget`](crate::FastraceCompatLayer::with_span_memory_budget).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpillPolicy {
+    /// The oldest events of the span are dropped to make room for new properties and events.
+    ///
+    /// To allow this, events are held back and only attached to the span when it closes, so
+    /// their timestamp is the closing time of the span. Their original time is kept in an
+    /// `event.time_unix_ns` property, which [`BackdatingReporter`](crate::BackdatingReporter)
+    /// restores.
+    DropOldestEvents,
+    /// Property values and event fields recorded once the budget is exceeded are truncated to
+    /// the given number of bytes.
+    TruncateValues(usize),
+    /// Properties and events recorded once the budget is exceeded are discarded.
+    StopRecording,
+}
+
+/// The memory budget of a bridged span, stored in its registry extensions.
+///
+/// The size of a property is approximated by the length of its key and value, and the size of an
+/// event by the length of its name and properties.
+pub(crate) struct SpanBudget {
+    limit: usize,
+    policy: SpillPolicy,
+    used: usize,
+    held_events: VecDeque<(usize, fastrace::Event)>,
+    dropped_events: usize,
+    dropped_properties: usize,
+    truncated_values: usize,
+}
+
+impl SpanBudget {
+    pub(crate) fn new(limit: usize, policy: SpillPolicy) -> Self {
+        SpanBudget {
+            limit,
+            policy,
+            used: 0,
+            held_events: VecDeque::new(),
+            dropped_events: 0,
+            dropped_properties: 0,
+            truncated_values: 0,
+        }
+    }
+
+    /// Accounts for properties about to be recorded on the span, applying the spill policy to
+    /// them if the budget is exceeded.
+    pub(crate) fn admit_properties(&mut self, properties: &mut Vec<Property>) {
+        let size = properties_size(properties);
+        match self.policy {
+            SpillPolicy::DropOldestEvents => {
+                self.used += size;
+                self.drop_oldest_events();
+            }
+            SpillPolicy::TruncateValues(max_len) => {
+                if self.used + size > self.limit {
+                    self.truncate_values(properties, max_len);
+                }
+                self.used += properties_size(properties);
+            }
+            SpillPolicy::StopRecording => {
+                if self.used + size > self.limit {
+                    self.dropped_properties += properties.len();
+                    properties.clear();
+                } else {
+                    self.used += size;
+                }
+            }
+        }
+    }
+
+    /// Accounts for an event about to be added to the span, applying the spill policy to it if
+    /// the budget is exceeded.
+    ///
+    /// Returns the event if it should be added to the span right away.
+    pub(crate) fn admit_event(
+        &mut self,
+        name: Cow<'static, str>,
+        mut properties: Vec<Property>,
+    ) -> Option<fastrace::Event> {
+        let size = name.len() + properties_size(&properties);
+        match self.policy {
+            SpillPolicy::DropOldestEvents => {
+                properties.push(event_time_now());
+                let event = fastrace::Event::new(name).with_properties(|| properties);
+                self.held_events.push_back((size, event));
+                self.used += size;
+                self.drop_oldest_events();
+                None
+            }
+            SpillPolicy::TruncateValues(max_len) => {
+                if self.used + size > self.limit {
+                    self.truncate_values(&mut properties, max_len);
+                }
+                self.used += name.len() + properties_size(&properties);
+                Some(fastrace::Event::new(name).with_properties(|| properties))
+            }
+            SpillPolicy::StopRecording => {
+                if self.used + size > self.limit {
+                    self.dropped_events += 1;
+                    return None;
+                }
+                self.used += size;
+                Some(fastrace::Event::new(name).with_properties(|| properties))
+            }
+        }
+    }
+
+    /// Adds the held back events and the counters of discarded data to the span.
+    pub(crate) fn finish(self, fastrace_span: &fastrace::Span) {
+        for (_, event) in self.held_events {
+            fastrace_span.add_event(event);
+        }
+
+        let counters = [
+            ("budget.dropped_events", self.dropped_events),
+            ("budget.dropped_properties", self.dropped_properties),
+            ("budget.truncated_values", self.truncated_values),
+        ];
+        fastrace_span.add_properties(|| {
+            counters
+                .into_iter()
+                .filter(|(_, count)| *count > 0)
+                .map(|(key, count)| (key, count.to_string()))
+        });
+    }
+
+    fn drop_oldest_events(&mut self) {
+        while self.used > self.limit {
+            let Some((size, _)) = self.held_events.pop_front() else {
+                break;
+            };
+            self.used -= size;
+            self.dropped_events += 1;
+        }
+    }
+
+    fn truncate_values(&mut self, properties: &mut [Property], max_len: usize) {
+        for (_, value) in properties {
+            if truncate_value(value, max_len) {
+                self.truncated_values += 1;
+            }
+        }
+    }
+}
+
+/// Truncates `value` to at most `max_len` bytes, on a char boundary.
+///
+/// Returns `true` if the value was truncated.
+pub(crate) fn truncate_value(value: &mut Cow<'static, str>, max_len: usize) -> bool {
+    if value.len() <= max_len {
+        return false;
+    }
+    let mut end = max_len;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    value.to_mut().truncate(end);
+    true
+}
+
+fn properties_size(properties: &[Property]) -> usize {
+    properties
+        .iter()
+        .map(|(key, value)| key.len() + value.len())
+        .sum()
+}
@@ -0,0 +1,156 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::Property;
+
+thread_local! {
+    /// Where the spans closed within the innermost call to [`capture`] on this thread are
+    /// collected.
+    static CAPTURE: RefCell<Option<Sink>> = const { RefCell::new(None) };
+}
+
+pub(crate) type Sink = Arc<Mutex<Vec<CapturedSpan>>>;
+
+/// A span captured by [`capture`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedSpan {
+    /// The name of the span.
+    pub name: Cow<'static, str>,
+    /// The id of the tracing span.
+    pub id: u64,
+    /// The id of the closest captured ancestor of the span, if any.
+    pub parent_id: Option<u64>,
+    /// How long the span was open.
+    pub duration: Duration,
+    /// The properties of the span, including those recorded after it was created.
+    pub properties: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    /// The events recorded within the span.
+    pub events: Vec<CapturedEvent>,
+}
+
+/// An event captured by [`capture`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedEvent {
+    /// The name of the event.
+    pub name: Cow<'static, str>,
+    /// The properties of the event.
+    pub properties: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+}
+
+/// Runs `f`, and returns the spans bridged within it, instead of forwarding them to fastrace.
+///
+/// This is meant for ad-hoc debugging and examples, as it does not require a fastrace reporter.
+/// Spans created on the current thread while `f` runs are converted as usual, but collected in
+/// memory, along with their events, instead of being bridged to fastrace spans. They are
+/// returned in the order they closed; spans still open when `f` returns are not included. A
+/// [`FastraceCompatLayer`](crate::FastraceCompatLayer) must be installed in the current
+/// subscriber.
+///
+/// # Example
+///
+/// ```
+/// use fastrace_tracing::FastraceCompatLayer;
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let subscriber = tracing_subscriber::Registry::default().with(FastraceCompatLayer::new());
+/// let spans = tracing::subscriber::with_default(subscriber, || {
+///     fastrace_tracing::capture(|| {
+///         let _span = tracing::info_span!("request").entered();
+///         tracing::info!("handling request");
+///     })
+/// });
+///
+/// assert_eq!(spans.len(), 1);
+/// assert_eq!(spans[0].name, "request");
+/// assert_eq!(spans[0].events[0].name, "handling request");
+/// ```
+pub fn capture(f: impl FnOnce()) -> Vec<CapturedSpan> {
+    let sink = Sink::default();
+    let previous = CAPTURE.with(|capture| capture.replace(Some(sink.clone())));
+    {
+        let _restore = Restore(previous);
+        f();
+    }
+
+    let mut spans = sink.lock().unwrap_or_else(|e| e.into_inner());
+    std::mem::take(&mut *spans)
+}
+
+/// Restores the capture of the enclosing call to [`capture`], even if the closure panics.
+struct Restore(Option<Sink>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        let _ = CAPTURE.try_with(|capture| *capture.borrow_mut() = previous);
+    }
+}
+
+/// Returns `true` if the spans created on the current thread are captured.
+pub(crate) fn is_capturing() -> bool {
+    CAPTURE
+        .try_with(|capture| capture.borrow().is_some())
+        .unwrap_or(false)
+}
+
+/// Returns where the spans created on the current thread are collected, if they are captured.
+pub(crate) fn current_sink() -> Option<Sink> {
+    CAPTURE.try_with(|capture| capture.borrow().clone()).ok()?
+}
+
+/// Stored in the registry extensions of a span created within [`capture`], in place of its
+/// fastrace span.
+pub(crate) struct CapturingSpan {
+    span: CapturedSpan,
+    start: Instant,
+    sink: Sink,
+}
+
+impl CapturingSpan {
+    /// Starts capturing a span, to be collected into `sink` once it closes.
+    pub(crate) fn new(
+        sink: Sink,
+        name: Cow<'static, str>,
+        id: u64,
+        parent_id: Option<u64>,
+        properties: Vec<Property>,
+    ) -> Self {
+        CapturingSpan {
+            span: CapturedSpan {
+                name,
+                id,
+                parent_id,
+                duration: Duration::ZERO,
+                properties,
+                events: Vec::new(),
+            },
+            start: Instant::now(),
+            sink,
+        }
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.span.id
+    }
+
+    pub(crate) fn add_properties(&mut self, properties: Vec<Property>) {
+        self.span.properties.extend(properties);
+    }
+
+    pub(crate) fn add_event(&mut self, name: Cow<'static, str>, properties: Vec<Property>) {
+        self.span.events.push(CapturedEvent { name, properties });
+    }
+
+    /// Ends the span, collecting it into the [`capture`] it was created in.
+    pub(crate) fn finish(mut self) {
+        self.span.duration = self.start.elapsed();
+        self.sink
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(self.span);
+    }
+}
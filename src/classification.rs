@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use crate::budget::truncate_value;
+use crate::Property;
+
+/// The sensitivity of a field, assigned with [`FieldPolicy::with_field`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Classification {
+    /// The field can be forwarded anywhere. Fields are public unless classified otherwise.
+    Public,
+    /// The field is meant for internal consumers only.
+    Internal,
+    /// The field holds credentials or personal data.
+    Secret,
+}
+
+/// Describes how the values of the fields of a [`Classification`] are recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldAction {
+    /// The value is recorded as is.
+    Forward,
+    /// The value is truncated to the given number of bytes.
+    Truncate(usize),
+    /// The field is not recorded.
+    Drop,
+}
+
+/// Classifies fields by name and decides how each [`Classification`] is recorded, typically
+/// depending on the environment the application runs in.
+///
+/// The policy applies to the fields of spans and events, to baggage, and to the properties of
+/// [`PropertyProvider`](crate::PropertyProvider)s. It is installed with
+/// [`FastraceCompatLayer::with_field_policy`](crate::FastraceCompatLayer::with_field_policy).
+///
+/// # Example
+///
+/// ```
+/// use fastrace_tracing::Classification;
+/// use fastrace_tracing::FieldPolicy;
+///
+/// let policy = match std::env::var("APP_ENV").as_deref() {
+///     Ok("prod") => FieldPolicy::production(),
+///     Ok("staging") => FieldPolicy::staging(),
+///     _ => FieldPolicy::development(),
+/// };
+/// let policy = policy
+///     .with_field("user.id", Classification::Internal)
+///     .with_field("user.email", Classification::Secret)
+///     .with_field("auth.token", Classification::Secret);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FieldPolicy {
+    classifications: HashMap<String, Classification>,
+    public: FieldAction,
+    internal: FieldAction,
+    secret: FieldAction,
+}
+
+impl FieldPolicy {
+    /// The number of bytes secret values are truncated to by [`FieldPolicy::staging`].
+    const STAGING_SECRET_LEN: usize = 4;
+
+    /// Creates a policy dropping secret fields and forwarding the others.
+    pub fn production() -> Self {
+        Self {
+            classifications: HashMap::new(),
+            public: FieldAction::Forward,
+            internal: FieldAction::Forward,
+            secret: FieldAction::Drop,
+        }
+    }
+
+    /// Creates a policy truncating secret values to their first 4 bytes and forwarding the other
+    /// fields.
+    pub fn staging() -> Self {
+        Self {
+            secret: FieldAction::Truncate(Self::STAGING_SECRET_LEN),
+            ..Self::production()
+        }
+    }
+
+    /// Creates a policy forwarding all fields.
+    pub fn development() -> Self {
+        Self {
+            secret: FieldAction::Forward,
+            ..Self::production()
+        }
+    }
+
+    /// Assigns `classification` to the fields named `name`.
+    pub fn with_field(mut self, name: impl Into<String>, classification: Classification) -> Self {
+        self.classifications.insert(name.into(), classification);
+        self
+    }
+
+    /// Overrides how the fields of `classification` are recorded.
+    pub fn with_action(mut self, classification: Classification, action: FieldAction) -> Self {
+        match classification {
+            Classification::Public => self.public = action,
+            Classification::Internal => self.internal = action,
+            Classification::Secret => self.secret = action,
+        }
+        self
+    }
+
+    /// Drops or truncates `properties` according to their classification.
+    pub(crate) fn apply(&self, properties: &mut Vec<Property>) {
+        properties.retain_mut(|(key, value)| match self.action(key) {
+            FieldAction::Forward => true,
+            FieldAction::Truncate(max_len) => {
+                truncate_value(value, max_len);
+                true
+            }
+            FieldAction::Drop => false,
+        });
+    }
+
+    fn action(&self, key: &str) -> FieldAction {
+        match self.classifications.get(key) {
+            None | Some(Classification::Public) => self.public,
+            Some(Classification::Internal) => self.internal,
+            Some(Classification::Secret) => self.secret,
+        }
+    }
+}
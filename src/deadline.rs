@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use fastrace::prelude::SpanContext;
+use tracing_core::span;
+
+/// The minimum interval between two checks of the deadlines.
+const TICK: Duration = Duration::from_millis(10);
+
+/// The position of a bridged span in the schedule.
+///
+/// The sequence number tells apart spans scheduled at the same instant. As span ids are reused
+/// by the registry once a span closes, it also tells whether a scheduled id still refers to the
+/// same span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Slot {
+    due: Instant,
+    seq: u64,
+}
+
+/// A deadline of a bridged span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Deadline {
+    /// The span has been open for longer than the long-lived span threshold.
+    LongLived,
+    /// The span has timed out.
+    Timeout,
+    /// The next heartbeat of the span is due.
+    Heartbeat,
+}
+
+/// Schedules the deadlines of the bridged spans: their timeout, their heartbeats, and the
+/// report of the spans that stay open for too long.
+///
+/// Each span is scheduled once, at its earliest pending deadline. The schedule is checked at
+/// most once per tick, by whichever thread creates a span or records an event next, so no
+/// deadline fires while the application is idle.
+pub(crate) struct Deadlines {
+    long_lived_threshold: Option<Duration>,
+    timeout: Option<Duration>,
+    heartbeat_interval: Option<Duration>,
+    next_seq: AtomicU64,
+    created: Instant,
+    /// When the schedule is checked next, in nanoseconds since `created`.
+    next_check: AtomicU64,
+    schedule: Mutex<BTreeMap<Slot, span::Id>>,
+}
+
+/// The pending deadlines of a bridged span, stored in its registry extensions.
+pub(crate) struct SpanDeadlines {
+    seq: u64,
+    start: Instant,
+    context: Option<SpanContext>,
+    scheduled: Option<Instant>,
+    long_lived: Option<Instant>,
+    timeout: Option<Instant>,
+    heartbeat: Option<Instant>,
+    events: usize,
+}
+
+impl Default for Deadlines {
+    fn default() -> Self {
+        Deadlines {
+            long_lived_threshold: None,
+            timeout: None,
+            heartbeat_interval: None,
+            next_seq: AtomicU64::new(0),
+            created: Instant::now(),
+            next_check: AtomicU64::new(0),
+            schedule: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl Deadlines {
+    pub(crate) fn with_long_lived_threshold(self, threshold: Duration) -> Self {
+        Deadlines {
+            long_lived_threshold: Some(threshold),
+            ..self
+        }
+    }
+
+    pub(crate) fn with_timeout(self, timeout: Duration) -> Self {
+        Deadlines {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    pub(crate) fn with_heartbeat_interval(self, interval: Duration) -> Self {
+        Deadlines {
+            heartbeat_interval: Some(interval),
+            ..self
+        }
+    }
+
+    /// Returns `true` if heartbeats are enabled, which count the events of their span.
+    pub(crate) fn has_heartbeats(&self) -> bool {
+        self.heartbeat_interval.is_some()
+    }
+
+    /// Schedules the deadlines of the span `id`, returning them to store in its extensions.
+    pub(crate) fn start(&self, id: span::Id, context: Option<SpanContext>) -> SpanDeadlines {
+        let start = Instant::now();
+        let mut deadlines = SpanDeadlines {
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            start,
+            context,
+            scheduled: None,
+            long_lived: self.long_lived_threshold.map(|threshold| start + threshold),
+            timeout: self.timeout.map(|timeout| start + timeout),
+            heartbeat: self.heartbeat_interval.map(|interval| start + interval),
+            events: 0,
+        };
+        self.schedule(id, &mut deadlines);
+        deadlines
+    }
+
+    /// Schedules the span `id` at its earliest pending deadline, if any.
+    pub(crate) fn schedule(&self, id: span::Id, deadlines: &mut SpanDeadlines) {
+        deadlines.scheduled = deadlines.next();
+        if let Some(due) = deadlines.scheduled {
+            let slot = Slot {
+                due,
+                seq: deadlines.seq,
+            };
+            self.lock().insert(slot, id);
+        }
+    }
+
+    /// Unschedules a span that closed.
+    pub(crate) fn finish(&self, deadlines: &SpanDeadlines) {
+        if let Some(due) = deadlines.scheduled {
+            let slot = Slot {
+                due,
+                seq: deadlines.seq,
+            };
+            self.lock().remove(&slot);
+        }
+    }
+
+    /// Removes and returns the spans with a deadline that has passed, along with their sequence
+    /// number, if the schedule is due to be checked.
+    ///
+    /// The deadlines that passed are taken with [`Deadlines::take_passed`], after which the span
+    /// must be scheduled again.
+    pub(crate) fn take_due(&self) -> Vec<(span::Id, u64)> {
+        if !self.check_due() {
+            return Vec::new();
+        }
+        let now = Instant::now();
+        let mut schedule = self.lock();
+        let mut due = Vec::new();
+        while let Some(entry) = schedule.first_entry() {
+            if entry.key().due > now {
+                break;
+            }
+            let (slot, id) = entry.remove_entry();
+            due.push((id, slot.seq));
+        }
+        due
+    }
+
+    /// Takes the deadlines of a span that have passed. The next heartbeat is due one interval
+    /// later, and no heartbeat is due once the span timed out.
+    pub(crate) fn take_passed(&self, deadlines: &mut SpanDeadlines) -> Vec<Deadline> {
+        let now = Instant::now();
+        let mut passed = Vec::new();
+        if deadlines.long_lived.is_some_and(|due| due <= now) {
+            deadlines.long_lived = None;
+            passed.push(Deadline::LongLived);
+        }
+        if deadlines.timeout.is_some_and(|due| due <= now) {
+            deadlines.timeout = None;
+            deadlines.heartbeat = None;
+            passed.push(Deadline::Timeout);
+        }
+        if deadlines.heartbeat.is_some_and(|due| due <= now) {
+            deadlines.heartbeat = self.heartbeat_interval.map(|interval| now + interval);
+            passed.push(Deadline::Heartbeat);
+        }
+        passed
+    }
+
+    /// Returns whether the schedule is due to be checked, letting only one thread check it per
+    /// tick.
+    fn check_due(&self) -> bool {
+        let now = self.created.elapsed().as_nanos() as u64;
+        let next = self.next_check.load(Ordering::Relaxed);
+        now >= next
+            && self
+                .next_check
+                .compare_exchange(
+                    next,
+                    now.saturating_add(TICK.as_nanos() as u64),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<Slot, span::Id>> {
+        self.schedule.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl SpanDeadlines {
+    /// Returns the sequence number the span was scheduled with.
+    pub(crate) fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Returns the context of the bridged span.
+    pub(crate) fn context(&self) -> Option<SpanContext> {
+        self.context
+    }
+
+    /// Returns how long the span has been open.
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Returns the number of events recorded within the span.
+    pub(crate) fn events(&self) -> usize {
+        self.events
+    }
+
+    /// Counts an event recorded within the span.
+    pub(crate) fn record_event(&mut self) {
+        self.events += 1;
+    }
+
+    /// Returns the earliest pending deadline.
+    fn next(&self) -> Option<Instant> {
+        [self.long_lived, self.timeout, self.heartbeat]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+}
@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+use fastrace::prelude::SpanContext;
+use tracing_core::Metadata;
+
+/// Reports a bridged span that has been open for `open_for`, longer than the long-lived span
+/// threshold, as a `long_lived_span` span in the trace of the offending span.
+pub(crate) fn report_long_lived_span(
+    meta: &'static Metadata<'static>,
+    context: Option<SpanContext>,
+    open_for: Duration,
+) {
+    let parent = context.unwrap_or_else(SpanContext::random);
+    let _report = fastrace::Span::root("long_lived_span", parent).with_properties(|| {
+        [
+            ("span.name", meta.name().to_string()),
+            ("span.target", meta.target().to_string()),
+            ("span.open_duration_ns", open_for.as_nanos().to_string()),
+        ]
+    });
+}
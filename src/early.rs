@@ -0,0 +1,168 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::Weak;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use fastrace::collector::Config;
+use fastrace::collector::Reporter;
+use fastrace::prelude::SpanContext;
+
+use crate::Property;
+use crate::orphan::PendingSpan;
+use crate::orphan::emit_pending_spans;
+
+static REPORTER_READY: AtomicBool = AtomicBool::new(false);
+
+/// The [`EarlyBuffer`]s of the layers created so far, replayed by [`set_reporter`].
+static BUFFERS: Mutex<Vec<Weak<EarlyBuffer>>> = Mutex::new(Vec::new());
+
+/// Returns `true` once [`set_reporter`] has been called.
+pub(crate) fn is_reporter_ready() -> bool {
+    REPORTER_READY.load(Ordering::Relaxed)
+}
+
+/// Sets the fastrace reporter, and replays the spans held back by
+/// [`FastraceCompatLayer::with_early_buffer`](crate::FastraceCompatLayer::with_early_buffer).
+///
+/// This calls [`fastrace::set_reporter`], and must be used instead of it for the layer to know
+/// that reporting is live. The buffered spans whose ancestors have all closed are replayed
+/// before this returns; the others are replayed once their last open ancestor closes.
+///
+/// # Example
+///
+/// ```
+/// use fastrace::collector::Config;
+/// use fastrace::collector::ConsoleReporter;
+///
+/// fastrace_tracing::set_reporter(ConsoleReporter, Config::default());
+/// ```
+pub fn set_reporter(reporter: impl Reporter, config: Config) {
+    fastrace::set_reporter(reporter, config);
+    REPORTER_READY.store(true, Ordering::Relaxed);
+
+    let buffers: Vec<_> = {
+        let mut buffers = BUFFERS.lock().unwrap_or_else(|e| e.into_inner());
+        buffers.retain(|buffer| buffer.strong_count() > 0);
+        buffers.iter().filter_map(Weak::upgrade).collect()
+    };
+    for buffer in buffers {
+        buffer.replay();
+    }
+}
+
+/// Stored in the registry extensions of a [`PendingSpan`] held back by the [`EarlyBuffer`].
+pub(crate) struct EarlySpan;
+
+/// Holds the spans created before the fastrace reporter was set, until reporting is live.
+pub(crate) struct EarlyBuffer {
+    capacity: usize,
+    next_seq: AtomicU64,
+    len: AtomicUsize,
+    state: Mutex<EarlyState>,
+}
+
+#[derive(Default)]
+struct EarlyState {
+    /// The spans that are still open.
+    open: HashSet<u64>,
+    /// The spans that have closed, but have not been emitted yet.
+    closed: Vec<PendingSpan>,
+}
+
+impl EarlyBuffer {
+    /// Creates a buffer, registered to be replayed by [`set_reporter`].
+    pub(crate) fn new(capacity: usize) -> Arc<Self> {
+        let buffer = Arc::new(EarlyBuffer {
+            capacity,
+            next_seq: AtomicU64::new(0),
+            len: AtomicUsize::new(0),
+            state: Mutex::new(EarlyState::default()),
+        });
+        BUFFERS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Arc::downgrade(&buffer));
+        buffer
+    }
+
+    /// Returns `true` if new spans should be buffered, i.e., reporting is not live yet.
+    pub(crate) fn is_buffering(&self) -> bool {
+        !is_reporter_ready()
+    }
+
+    /// Starts holding back a new span, or returns `None` if the buffer is full.
+    ///
+    /// Open spans count toward the capacity, so that the buffer cannot grow past it.
+    pub(crate) fn new_pending_span(
+        &self,
+        name: Cow<'static, str>,
+        parent: Option<u64>,
+        properties: Vec<Property>,
+    ) -> Option<PendingSpan> {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut state = self.lock();
+            if state.open.len() + state.closed.len() >= self.capacity {
+                return None;
+            }
+            state.open.insert(seq);
+        }
+        Some(PendingSpan::new(seq, parent, name, properties))
+    }
+
+    /// Buffers a closed span, and replays the buffered spans if reporting is live.
+    pub(crate) fn push(&self, mut pending: PendingSpan) {
+        pending.close();
+        {
+            let mut state = self.lock();
+            state.open.remove(&pending.seq());
+            state.closed.push(pending);
+            self.len.store(state.closed.len(), Ordering::Relaxed);
+        }
+        self.replay();
+    }
+
+    /// Emits the buffered spans as new traces if reporting is live.
+    ///
+    /// Spans with an open ancestor are held back until the ancestor closes, so that traces are
+    /// emitted whole.
+    pub(crate) fn replay(&self) {
+        if self.len.load(Ordering::Relaxed) == 0 || !is_reporter_ready() {
+            return;
+        }
+
+        let ready = {
+            let mut state = self.lock();
+            let mut pending = std::mem::take(&mut state.closed);
+            pending.sort_by_key(PendingSpan::seq);
+
+            // Parents are created before their children, so they come first.
+            let mut held = HashSet::new();
+            let (held_back, ready): (Vec<_>, Vec<_>) = pending.into_iter().partition(|span| {
+                let is_held = span
+                    .parent()
+                    .is_some_and(|parent| state.open.contains(&parent) || held.contains(&parent));
+                if is_held {
+                    held.insert(span.seq());
+                }
+                is_held
+            });
+            state.closed = held_back;
+            self.len.store(state.closed.len(), Ordering::Relaxed);
+            ready
+        };
+        emit_pending_spans(ready, |name| {
+            fastrace::Span::root(name, SpanContext::random())
+        });
+    }
+
+    fn lock(&self) -> MutexGuard<'_, EarlyState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
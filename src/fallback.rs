@@ -0,0 +1,44 @@
+use std::fmt::Write;
+
+use tracing_core::Level;
+use tracing_core::Metadata;
+
+use crate::Property;
+
+/// Forwards an event that could not be attached to any fastrace span to the `log` crate, as
+/// `<name> key=value ...`.
+pub(crate) fn log_event(meta: &Metadata<'_>, name: &str, properties: &[Property]) {
+    let level = match *meta.level() {
+        Level::ERROR => log::Level::Error,
+        Level::WARN => log::Level::Warn,
+        Level::INFO => log::Level::Info,
+        Level::DEBUG => log::Level::Debug,
+        Level::TRACE => log::Level::Trace,
+    };
+    let log_meta = log::Metadata::builder()
+        .level(level)
+        .target(meta.target())
+        .build();
+    let logger = log::logger();
+    if !logger.enabled(&log_meta) {
+        return;
+    }
+
+    let mut message = name.to_string();
+    for (key, value) in properties {
+        let _ = write!(message, " {}={}", key, value);
+    }
+    #[cfg(not(feature = "strip-location"))]
+    let (module_path, file, line) = (meta.module_path(), meta.file(), meta.line());
+    #[cfg(feature = "strip-location")]
+    let (module_path, file, line) = (None, None, None);
+    logger.log(
+        &log::Record::builder()
+            .metadata(log_meta)
+            .module_path(module_path)
+            .file(file)
+            .line(line)
+            .args(format_args!("{}", message))
+            .build(),
+    );
+}
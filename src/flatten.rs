@@ -0,0 +1,82 @@
+use crate::Property;
+
+/// Records the Debug output `value` of the field `key` as `key.0`, `key.1`, ... properties, if it
+/// is a list, e.g., `["a", "b"]`.
+///
+/// At most `max_elements` elements are recorded. The length of longer lists is recorded as
+/// `key.len`. Returns `false` if `value` is not a list or is empty, in which case the field
+/// should be recorded as an ordinary property.
+pub(crate) fn flatten_list(
+    properties: &mut Vec<Property>,
+    key: &str,
+    value: &str,
+    max_elements: usize,
+) -> bool {
+    let Some(elements) = split_list(value).filter(|elements| !elements.is_empty()) else {
+        return false;
+    };
+
+    let len = elements.len();
+    for (i, element) in elements.into_iter().take(max_elements).enumerate() {
+        properties.push((format!("{}.{}", key, i).into(), unquote(element).into()));
+    }
+    if len > max_elements {
+        properties.push((format!("{}.len", key).into(), len.to_string().into()));
+    }
+    true
+}
+
+/// Splits the Debug output of a list into the Debug output of its elements.
+///
+/// Commas nested in brackets, parentheses, braces, or string and character literals do not
+/// separate elements. Returns `None` if `value` is not a list.
+fn split_list(value: &str) -> Option<Vec<&str>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?;
+    let mut elements = Vec::new();
+    if inner.trim().is_empty() {
+        return Some(elements);
+    }
+
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        if let Some(q) = quote {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == q => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '[' | '(' | '{' => depth += 1,
+            ']' | ')' | '}' => depth = depth.checked_sub(1)?,
+            ',' if depth == 0 => {
+                elements.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 || quote.is_some() {
+        return None;
+    }
+    // Pretty-printed lists end with a trailing comma.
+    let last = inner[start..].trim();
+    if !last.is_empty() {
+        elements.push(last);
+    }
+    Some(elements)
+}
+
+/// Removes the quotes around a string literal without escape sequences, e.g., `"a"`.
+fn unquote(element: &str) -> String {
+    match element.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(unquoted) if !unquoted.contains(['\\', '"']) => unquoted.to_string(),
+        _ => element.to_string(),
+    }
+}
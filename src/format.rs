@@ -0,0 +1,53 @@
+use tracing_core::Event;
+use tracing_core::span::Attributes;
+use tracing_core::span::Record;
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::FormatFields;
+use tracing_subscriber::fmt::format::Writer;
+
+/// The property carrying the fields of a span or event, as rendered by the formatter configured
+/// with [`FastraceCompatLayer::with_fields_formatter`](crate::FastraceCompatLayer::with_fields_formatter).
+pub(crate) const FIELD_FORMATTED_FIELDS: &str = "tracing.fields";
+
+/// Renders sets of fields with a [`FormatFields`] implementation.
+///
+/// [`FormatFields`] is generic over the fields it formats, so it is wrapped in this trait to be
+/// stored on the layer.
+pub(crate) trait FieldsFormatter: Send + Sync + 'static {
+    fn format_attributes(&self, attrs: &Attributes<'_>) -> Option<String>;
+
+    fn format_record(&self, record: &Record<'_>) -> Option<String>;
+
+    fn format_event(&self, event: &Event<'_>) -> Option<String>;
+}
+
+impl<N> FieldsFormatter for N
+where
+    N: for<'writer> FormatFields<'writer> + Send + Sync + 'static,
+{
+    fn format_attributes(&self, attrs: &Attributes<'_>) -> Option<String> {
+        format_fields(self, attrs)
+    }
+
+    fn format_record(&self, record: &Record<'_>) -> Option<String> {
+        format_fields(self, record)
+    }
+
+    fn format_event(&self, event: &Event<'_>) -> Option<String> {
+        format_fields(self, event)
+    }
+}
+
+/// Renders `fields` with `formatter`, returning `None` if there is nothing to render or
+/// formatting fails.
+fn format_fields<N, R>(formatter: &N, fields: R) -> Option<String>
+where
+    N: for<'writer> FormatFields<'writer>,
+    R: RecordFields,
+{
+    let mut rendered = String::new();
+    formatter
+        .format_fields(Writer::new(&mut rendered), fields)
+        .ok()?;
+    (!rendered.is_empty()).then_some(rendered)
+}
@@ -0,0 +1,41 @@
+use std::hash::Hasher;
+
+use siphasher::sip128::Hasher128;
+use siphasher::sip128::SipHasher24;
+
+use crate::Property;
+
+/// Replaces the values of selected fields with a salted hash.
+///
+/// Values are hashed with SipHash-2-4 keyed by the salt, so that equal values map to equal
+/// hashes for a given salt, while the original values cannot be recovered without the salt.
+pub(crate) struct FieldHasher {
+    fields: Vec<String>,
+    key: [u8; 16],
+}
+
+impl FieldHasher {
+    pub(crate) fn new(fields: Vec<String>, salt: &[u8]) -> Self {
+        let mut hasher = SipHasher24::new_with_key(&[0; 16]);
+        hasher.write(salt);
+        FieldHasher {
+            fields,
+            key: hasher.finish128().as_u128().to_le_bytes(),
+        }
+    }
+
+    /// Replaces the values of the hashed fields among `properties`.
+    pub(crate) fn apply(&self, properties: &mut [Property]) {
+        for (key, value) in properties {
+            if self.fields.iter().any(|field| field == key) {
+                *value = self.hash(value).into();
+            }
+        }
+    }
+
+    fn hash(&self, value: &str) -> String {
+        let mut hasher = SipHasher24::new_with_key(&self.key);
+        hasher.write(value.as_bytes());
+        format!("{:032x}", hasher.finish128().as_u128())
+    }
+}
@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+use fastrace::prelude::SpanContext;
+
+/// Reports the progress of the running span named `name` as a `span.heartbeat` span, in the
+/// trace of the span.
+pub(crate) fn emit_heartbeat(
+    name: &str,
+    context: Option<SpanContext>,
+    elapsed: Duration,
+    events: usize,
+) {
+    let parent = context.unwrap_or_else(SpanContext::random);
+    let _heartbeat = fastrace::Span::root("span.heartbeat", parent).with_properties(|| {
+        [
+            ("span.name", name.to_string()),
+            ("span.elapsed_ns", elapsed.as_nanos().to_string()),
+            ("span.event_count", events.to_string()),
+        ]
+    });
+}
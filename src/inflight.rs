@@ -0,0 +1,60 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// Describes what happens to new spans while too many bridged spans are open, configured with
+/// [`FastraceCompatLayer::with_max_spans_in_flight`](crate::FastraceCompatLayer::with_max_spans_in_flight).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InFlightPolicy {
+    /// New spans are not bridged. Their children and events are attached to the nearest bridged
+    /// ancestor, as with [`SpanAction::Discard`](crate::SpanAction::Discard).
+    DropNew,
+    /// New spans are recorded as events on the nearest bridged ancestor, as with
+    /// [`SpanAction::EventOnly`](crate::SpanAction::EventOnly).
+    Event,
+    /// New spans are bridged to no-op fastrace spans, so that they and everything created within
+    /// them are not recorded.
+    Noop,
+}
+
+/// Counts the bridged spans that are open, to bound their number.
+pub(crate) struct InFlightSpans {
+    max: usize,
+    policy: InFlightPolicy,
+    count: Arc<AtomicUsize>,
+}
+
+impl InFlightSpans {
+    pub(crate) fn new(max: usize, policy: InFlightPolicy) -> Self {
+        InFlightSpans {
+            max,
+            policy,
+            count: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> InFlightPolicy {
+        self.policy
+    }
+
+    /// Counts a new bridged span, until the returned guard is dropped with the span.
+    ///
+    /// Returns `None` if the maximum number of open spans is reached.
+    pub(crate) fn acquire(&self) -> Option<InFlightGuard> {
+        if self.count.fetch_add(1, Ordering::Relaxed) >= self.max {
+            self.count.fetch_sub(1, Ordering::Relaxed);
+            return None;
+        }
+        Some(InFlightGuard(self.count.clone()))
+    }
+}
+
+/// Stored in the registry extensions of a bridged span, uncounting it once its registry data is
+/// dropped.
+pub(crate) struct InFlightGuard(Arc<AtomicUsize>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
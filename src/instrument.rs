@@ -0,0 +1,85 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use pin_project_lite::pin_project;
+
+use crate::with_fastrace_span;
+
+/// Extension trait allowing futures to be instrumented with a tracing span and its bridged
+/// fastrace span at once.
+///
+/// Instrumenting a future with [`tracing::Instrument`] alone only restores the tracing context
+/// when the future is polled, so fastrace spans created with a local parent inside the future
+/// may end up detached from the trace. Stacking [`tracing::Instrument`] with fastrace's
+/// `in_span` requires a separate fastrace span and careful ordering. [`FastraceInstrumented`]
+/// enters the tracing span and sets the fastrace span bridged from it as the local parent on
+/// every poll, keeping both contexts consistent across awaits and task moves.
+///
+/// # Example
+///
+/// ```
+/// use fastrace_tracing::FastraceInstrument;
+///
+/// async fn handle_request() {
+///     // Both tracing and fastrace local spans created here are children of `request`.
+///     let _span = fastrace::local::LocalSpan::enter_with_local_parent("fastrace child");
+///     tracing::info!("handling request");
+/// }
+///
+/// let future = handle_request().fastrace_instrument(tracing::info_span!("request"));
+/// ```
+pub trait FastraceInstrument: Sized {
+    /// Instruments this future with `span`.
+    fn fastrace_instrument(self, span: tracing::Span) -> FastraceInstrumented<Self> {
+        FastraceInstrumented { inner: self, span }
+    }
+
+    /// Instruments this future with the current tracing span.
+    fn in_current_fastrace_span(self) -> FastraceInstrumented<Self> {
+        self.fastrace_instrument(tracing::Span::current())
+    }
+}
+
+impl<T: Future> FastraceInstrument for T {}
+
+pin_project! {
+    /// A future instrumented with a tracing span and its bridged fastrace span.
+    ///
+    /// This is created by [`FastraceInstrument::fastrace_instrument`].
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub struct FastraceInstrumented<T> {
+        #[pin]
+        inner: T,
+        span: tracing::Span,
+    }
+}
+
+impl<T> FastraceInstrumented<T> {
+    /// Returns the tracing span this future is instrumented with.
+    pub fn span(&self) -> &tracing::Span {
+        &self.span
+    }
+
+    /// Returns a reference to the inner future.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Consumes `self`, returning the inner future.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Future> Future for FastraceInstrumented<T> {
+    type Output = T::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _enter = this.span.enter();
+        let _guard = with_fastrace_span(this.span, |span| span.set_local_parent());
+        this.inner.poll(cx)
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::mem;
+use std::sync::LazyLock;
+use std::sync::RwLock;
+
+/// A naming convention for property keys, configured with
+/// [`FastraceCompatLayer::with_key_case`](crate::FastraceCompatLayer::with_key_case).
+///
+/// Words are delimited by `_`, `-`, and case changes. With [`KeyCase::Snake`] and
+/// [`KeyCase::Camel`], dots are kept as namespace separators, e.g., `http.statusCode` becomes
+/// `http.status_code` in snake case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyCase {
+    /// Words are lowercase and separated by `_`, e.g., `request_id`.
+    Snake,
+    /// Words are lowercase and separated by `.`, e.g., `request.id`.
+    Dot,
+    /// Words after the first one are capitalized, e.g., `requestId`.
+    Camel,
+}
+
+impl KeyCase {
+    /// Returns `key` converted to this case.
+    ///
+    /// Converted keys are interned, so that each of them is only allocated once for the lifetime
+    /// of the process. Keys are field names, whose number is bounded by the number of callsites.
+    pub(crate) fn convert(self, key: &'static str) -> &'static str {
+        static CONVERTED: LazyLock<RwLock<HashMap<(KeyCase, &'static str), &'static str>>> =
+            LazyLock::new(Default::default);
+
+        let interned = CONVERTED
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&(self, key))
+            .copied();
+        interned.unwrap_or_else(|| {
+            CONVERTED
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .entry((self, key))
+                .or_insert_with(|| {
+                    let converted = self.convert_uncached(key);
+                    if converted == key {
+                        key
+                    } else {
+                        Box::leak(converted.into_boxed_str())
+                    }
+                })
+        })
+    }
+
+    fn convert_uncached(self, key: &str) -> String {
+        let segments = key.split('.').map(|segment| {
+            let words = words(segment);
+            match self {
+                KeyCase::Snake => words.join("_"),
+                KeyCase::Dot => words.join("."),
+                KeyCase::Camel => {
+                    let mut camel = String::with_capacity(segment.len());
+                    for (i, word) in words.iter().enumerate() {
+                        let mut chars = word.chars();
+                        match chars.next() {
+                            Some(first) if i > 0 => {
+                                camel.extend(first.to_uppercase());
+                                camel.push_str(chars.as_str());
+                            }
+                            _ => camel.push_str(word),
+                        }
+                    }
+                    camel
+                }
+            }
+        });
+        segments.collect::<Vec<_>>().join(".")
+    }
+}
+
+/// Splits `segment` into lowercase words, delimited by `_`, `-`, and case changes.
+///
+/// An uppercase letter starts a new word if it follows a lowercase letter or a digit, or if it
+/// ends a run of uppercase letters followed by a lowercase one, e.g., `HTTPServer` is split into
+/// `http` and `server`.
+fn words(segment: &str) -> Vec<String> {
+    let chars = segment.chars().collect::<Vec<_>>();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' {
+            if !word.is_empty() {
+                words.push(mem::take(&mut word));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lowercase = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lowercase)
+            {
+                words.push(mem::take(&mut word));
+            }
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
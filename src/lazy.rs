@@ -0,0 +1,60 @@
+use std::fmt;
+
+use tracing_core::field;
+
+/// The value of a span field whose conversion to a property is deferred.
+pub(crate) enum RawValue {
+    Bool(bool),
+    F64(f64),
+    I64(i64),
+    U64(u64),
+    Str(String),
+    Bytes(Vec<u8>),
+    /// The Debug output of the value, which has to be formatted right away because tracing only
+    /// lends field values for the duration of the callback.
+    Debug(String),
+}
+
+/// Span fields recorded when the span was created, whose conversion to properties is deferred
+/// until the span records its first event or closes.
+///
+/// The fields are converted by replaying them through a [`field::Visit`], so that they are
+/// recorded exactly as if they had been visited when the span was created.
+#[derive(Default)]
+pub(crate) struct LazyFields {
+    fields: Vec<(field::Field, RawValue)>,
+}
+
+impl LazyFields {
+    pub(crate) fn push(&mut self, field: &field::Field, value: RawValue) {
+        self.fields.push((field.clone(), value));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Records the deferred fields on `visitor`.
+    pub(crate) fn replay(self, visitor: &mut dyn field::Visit) {
+        for (field, value) in self.fields {
+            match value {
+                RawValue::Bool(value) => visitor.record_bool(&field, value),
+                RawValue::F64(value) => visitor.record_f64(&field, value),
+                RawValue::I64(value) => visitor.record_i64(&field, value),
+                RawValue::U64(value) => visitor.record_u64(&field, value),
+                RawValue::Str(value) => visitor.record_str(&field, &value),
+                RawValue::Bytes(value) => visitor.record_bytes(&field, &value),
+                RawValue::Debug(value) => visitor.record_debug(&field, &Formatted(&value)),
+            }
+        }
+    }
+}
+
+/// Debug output that has already been formatted.
+struct Formatted<'a>(&'a str);
+
+impl fmt::Debug for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
@@ -0,0 +1,30 @@
+use std::borrow::Cow;
+
+use tracing_core::Level;
+use tracing_core::Metadata;
+
+use crate::presets::target_matches;
+
+/// The most verbose level recorded for each target, configured with
+/// [`FastraceCompatLayer::with_target_levels`](crate::FastraceCompatLayer::with_target_levels).
+pub(crate) struct TargetLevels {
+    levels: Vec<(Cow<'static, str>, Level)>,
+}
+
+impl TargetLevels {
+    pub(crate) fn new(levels: Vec<(Cow<'static, str>, Level)>) -> Self {
+        TargetLevels { levels }
+    }
+
+    /// Returns `true` if a span or event with the given metadata should be recorded.
+    ///
+    /// The longest target matching the metadata applies. Metadata matching no target is
+    /// recorded.
+    pub(crate) fn is_enabled(&self, meta: &Metadata<'_>) -> bool {
+        self.levels
+            .iter()
+            .filter(|(target, _)| target_matches(meta.target(), target))
+            .max_by_key(|(target, _)| target.len())
+            .map_or(true, |(_, level)| meta.level() <= level)
+    }
+}
@@ -1,25 +1,196 @@
 #![doc = include_str!("../README.md")]
 
+mod adaptive;
+mod aggregate;
+mod backdate;
+mod baggage;
+mod batch;
+mod budget;
+mod capture;
+mod classification;
+mod deadline;
+mod diagnostics;
+mod early;
+#[cfg(feature = "log")]
+mod fallback;
+mod flatten;
+#[cfg(feature = "fmt")]
+mod format;
+mod hashing;
+mod heartbeat;
+mod inflight;
+mod instrument;
+mod key_case;
+mod lazy;
+mod levels;
+mod naming;
+mod orphan;
+#[cfg(feature = "opentelemetry")]
+mod otel;
+mod output;
+mod pairs;
+mod panic;
+mod path;
+mod policy;
+mod presets;
+mod propagation;
+mod provider;
+mod reentrancy;
+mod resource;
+mod sanitize;
+mod segment;
+mod shared;
+#[cfg(feature = "shutdown")]
+mod shutdown;
+#[cfg(feature = "tokio")]
+mod spawn;
+mod split;
+mod stacktrace;
+mod stats;
+mod summary;
+#[cfg(feature = "tokio")]
+mod task_local;
+mod telemetry;
+mod tenant;
+mod thread_root;
+mod timeout;
+mod value;
+
+pub use adaptive::AdaptiveSampler;
+pub use backdate::BackdatingReporter;
+pub use baggage::get_baggage;
+pub use baggage::set_baggage;
+pub use baggage::Baggage;
+pub use budget::SpillPolicy;
+pub use capture::CapturedEvent;
+pub use capture::CapturedSpan;
+pub use capture::capture;
+pub use classification::Classification;
+pub use classification::FieldAction;
+pub use classification::FieldPolicy;
+pub use early::set_reporter;
+pub use inflight::InFlightPolicy;
+pub use instrument::FastraceInstrument;
+pub use instrument::FastraceInstrumented;
+pub use key_case::KeyCase;
+pub use naming::RootNaming;
+#[cfg(feature = "opentelemetry")]
+pub use otel::OtelPropagator;
+pub use output::ReturnCapture;
+pub use pairs::EventToSpanBridge;
+pub use panic::install_panic_hook;
+pub use path::PathStyle;
+pub use policy::Decision;
+pub use policy::SpanAction;
+pub use presets::NoiseFilter;
+pub use presets::Presets;
+pub use propagation::B3;
+pub use propagation::Datadog;
+pub use propagation::XRay;
+pub use provider::PropertyProvider;
+pub use reentrancy::GuardedReporter;
+pub use reentrancy::Suppressed;
+pub use reentrancy::suppressed;
+pub use reentrancy::suppressed_async;
+pub use sanitize::ControlCharacters;
+pub use shared::SharedLayer;
+#[cfg(feature = "shutdown")]
+pub use shutdown::ShutdownHandle;
+#[cfg(feature = "tokio")]
+pub use spawn::spawn;
+pub use split::RootSplit;
+pub use stacktrace::StacktraceFormat;
+pub use stats::Stats;
+pub use tenant::TenantResolver;
+pub use timeout::LateRecords;
+pub use value::FieldValue;
+
+use std::any::TypeId;
 use std::borrow::Cow;
 use std::cell::LazyCell;
 use std::fmt;
 use std::marker;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::thread;
+use std::time::Duration;
 
 use fastrace::prelude::SpanContext;
+use fastrace::prelude::SpanId;
+use fastrace::prelude::TraceId;
 use tracing_core::field;
 use tracing_core::span::Attributes;
 use tracing_core::span::Id;
 use tracing_core::span::Record;
 use tracing_core::span::{self};
+use tracing_core::subscriber::Interest;
+use tracing_core::Dispatch;
 use tracing_core::Event;
+use tracing_core::Level;
+use tracing_core::Metadata;
 use tracing_core::Subscriber;
 use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::ExtensionsMut;
 use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::registry::SpanRef;
 use tracing_subscriber::Layer;
 
+use crate::aggregate::AggregatedSpan;
+use crate::aggregate::SpanAggregates;
+use crate::backdate::PROPERTY_EVENT_TIME;
+use crate::backdate::event_time_now;
+use crate::baggage::FIELD_BAGGAGE_PREFIX;
+use crate::batch::EventBatcher;
+use crate::budget::SpanBudget;
+use crate::capture::CapturingSpan;
+use crate::deadline::Deadline;
+use crate::deadline::Deadlines;
+use crate::deadline::SpanDeadlines;
+use crate::diagnostics::report_long_lived_span;
+use crate::early::EarlyBuffer;
+use crate::early::EarlySpan;
+use crate::flatten::flatten_list;
+#[cfg(feature = "fmt")]
+use crate::format::FIELD_FORMATTED_FIELDS;
+#[cfg(feature = "fmt")]
+use crate::format::FieldsFormatter;
+use crate::hashing::FieldHasher;
+use crate::heartbeat::emit_heartbeat;
+use crate::inflight::InFlightSpans;
+use crate::lazy::LazyFields;
+use crate::lazy::RawValue;
+use crate::levels::TargetLevels;
+use crate::orphan::OrphanBuffer;
+use crate::orphan::PendingSpan;
+use crate::output::InstrumentOutput;
+use crate::pairs::Edge;
+use crate::policy::Filters;
+use crate::policy::SpanActions;
+use crate::policy::SpanPolicy;
+use crate::provider::SpanProperties;
+use crate::resource::RootProperties;
+use crate::sanitize::Sanitizer;
+use crate::segment::SpanSegments;
+use crate::split::Roots;
+use crate::stats::Counters;
+use crate::summary::CloseSummary;
+use crate::telemetry::Callback;
+use crate::telemetry::SelfTelemetry;
+use crate::tenant::Tenant;
+use crate::value::FieldOptions;
+
 const FIELD_EXCEPTION_MESSAGE: &str = "exception.message";
 const FIELD_EXCEPTION_STACKTRACE: &str = "exception.stacktrace";
+const FIELD_LINKS: &str = "links";
+const FIELD_TIMESTAMP: &str = "timestamp";
+const FIELD_EVENT_TIME_UNIX_NANO: &str = "event.time_unix_nano";
+const FIELD_FASTRACE_ROOT: &str = "fastrace.root";
+const FIELD_FASTRACE_NAME: &str = "fastrace.name";
+const FIELD_FASTRACE_SKIP: &str = "fastrace.skip";
+const FIELD_FASTRACE_EVENT_ONLY: &str = "fastrace.event_only";
+const FIELD_FASTRACE_AS_SPAN: &str = "fastrace.as_span";
+const FIELD_SAMPLE_RATE: &str = "sample_rate";
 
 /// A compatibility layer for using libraries instrumented with
 /// `tokio-tracing` in applications using `fastrace`.
@@ -27,6 +198,38 @@ const FIELD_EXCEPTION_STACKTRACE: &str = "exception.stacktrace";
 /// This layer collects spans and events created using the tokio-tracing ecosystem and
 /// forwards them to fastrace's collectors.
 ///
+/// # Field conventions
+///
+/// Some span fields are interpreted by the layer instead of being recorded verbatim:
+///
+/// - `baggage.<key>`: attaches `<key>` as [`Baggage`], recorded as a property on this span and on
+///   every bridged span created within it.
+/// - `links`: one or more comma-separated W3C `traceparent`, single [`B3`], or [`XRay`] header
+///   values, recorded as `link.N.trace_id` and `link.N.span_id` properties.
+/// - `fastrace.name`: overrides the name of the bridged span, e.g., with the matched HTTP route.
+///   fastrace cannot rename a span once it has started, so a name recorded later with
+///   [`tracing::Span::record`] is only kept as a `fastrace.name` property, unless the span has been
+///   held back by the orphan buffer.
+/// - `fastrace.skip = true`: does not bridge this span. Its children and events are attached to the
+///   nearest bridged ancestor, as with [`SpanAction::Discard`].
+/// - `fastrace.event_only = true`: records this span as an event on the nearest bridged ancestor,
+///   as with [`SpanAction::EventOnly`].
+/// - `fastrace.root = true`: starts a new trace with a fresh trace id for this span, regardless of
+///   its parent or the fastrace local parent. This is useful at message queue consumer boundaries
+///   instrumented with `#[instrument]`.
+/// - `sample_rate = <ratio>`: bridges this span in the given ratio of traces, e.g., `0.01`, for
+///   very hot instrumentation points. The decision is derived from the trace id, so that it is the
+///   same for all spans of a trace. Spans that are not sampled are handled as with
+///   [`SpanAction::Discard`].
+///
+/// On events, a `fastrace.as_span = true` field records the event as a zero-duration child span
+/// of its span, carrying the properties of the event. This gives milestones their own node in the
+/// trace tree, as with [`with_events_as_spans`](FastraceCompatLayer::with_events_as_spans).
+///
+/// The layer runs no background thread. Time-based features, e.g., long-lived span detection,
+/// span timeouts, heartbeats, event batching, and self telemetry, are checked when spans or events
+/// are created, so nothing fires while the application is idle.
+///
 /// # Example
 ///
 /// ```
@@ -58,12 +261,373 @@ const FIELD_EXCEPTION_STACKTRACE: &str = "exception.stacktrace";
 /// tracing::info!("This event will be captured by fastrace");
 /// ```
 pub struct FastraceCompatLayer<S> {
-    location: bool,
-    with_threads: bool,
-    with_level: bool,
+    span_properties: SpanProperties,
+    with_tracing_ids: bool,
+    field_options: FieldOptions,
+    lazy_fields: bool,
+    ignore_span_fields: bool,
+    #[cfg(feature = "fmt")]
+    fields_formatter: Option<Box<dyn FieldsFormatter>>,
+    empty_field_placeholder: Option<Cow<'static, str>>,
+    sanitizer: Option<Box<Sanitizer>>,
+    root_properties: Option<Box<RootProperties>>,
+    #[cfg(feature = "tracing-opentelemetry")]
+    otel_data: bool,
+    filters: Option<Box<Filters>>,
+    span_actions: SpanActions,
+    span_drop_if: Option<Box<DropIf>>,
+    event_folding: Option<(Level, EventFolding)>,
+    event_batcher: Option<Box<EventBatcher>>,
+    local_parent_events: bool,
+    error_summary: bool,
+    close_summary: Option<Vec<Cow<'static, str>>>,
+    events_as_spans: bool,
+    event_to_span_bridge: Option<Box<EventToSpanBridge>>,
+    return_capture: Option<ReturnCapture>,
+    target_in_event_names: bool,
+    span_name_on_events: bool,
+    root_span_name_on_events: bool,
+    orphan_buffer: Option<Box<OrphanBuffer>>,
+    early_buffer: Option<Arc<EarlyBuffer>>,
+    #[cfg(feature = "log")]
+    log_fallback: bool,
+    max_children_per_span: Option<usize>,
+    max_depth: Option<usize>,
+    spans_in_flight: Option<Box<InFlightSpans>>,
+    deadlines: Option<Box<Deadlines>>,
+    span_segmentation: Option<(usize, Duration)>,
+    late_records: LateRecords,
+    span_memory_budget: Option<(usize, SpillPolicy)>,
+    max_properties_per_event: Option<usize>,
+    #[cfg(feature = "shutdown")]
+    shutdown: Option<ShutdownHandle>,
+    adaptive_sampler: Option<Box<AdaptiveSampler>>,
+    self_telemetry: Option<Box<SelfTelemetry>>,
+    counters: Counters,
+    shadow_mode: AtomicBool,
+    roots: Option<Box<Roots>>,
+    tenant_resolver: Option<TenantResolver>,
+    get_context: WithContext,
     _phantom: marker::PhantomData<S>,
 }
 
+/// Describes how low-severity events are folded into their span by
+/// [`FastraceCompatLayer::with_event_folding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventFolding {
+    /// Each event is recorded as a span property named `log.<level>.<N>` whose value is the
+    /// event message, where `N` counts the folded events of that level within the span.
+    Properties,
+    /// Only the number of folded events is recorded, as a span property named
+    /// `log.<level>.count` added when the span closes.
+    Count,
+}
+
+/// Gives access to the registry extensions of a span from outside of the layer.
+///
+/// The layer hands out a pointer to this through [`Layer::downcast_raw`], which is the same
+/// approach `tracing-opentelemetry` uses to expose its span data.
+struct WithContext(GetContextFn);
+
+type GetContextFn = fn(&Dispatch, &span::Id, &mut dyn FnMut(&mut ExtensionsMut<'_>));
+
+/// Runs `f` with the registry extensions of `span`, if the subscriber of `span` has a
+/// [`FastraceCompatLayer`] installed.
+pub(crate) fn with_span_extensions<R>(
+    span: &tracing::Span,
+    f: impl FnOnce(&mut ExtensionsMut<'_>) -> R,
+) -> Option<R> {
+    let mut f = Some(f);
+    let mut result = None;
+    span.with_subscriber(|(id, dispatch)| {
+        if let Some(get_context) = dispatch.downcast_ref::<WithContext>() {
+            (get_context.0)(dispatch, id, &mut |extensions| {
+                if let Some(f) = f.take() {
+                    result = Some(f(extensions));
+                }
+            });
+        }
+    });
+    result
+}
+
+/// Runs `f` with the fastrace span bridged from `span`.
+///
+/// This allows code that only has access to a tracing span to add fastrace-native properties,
+/// create child spans, or cancel the bridged span directly. Returns `None` if `span` was not
+/// bridged to a fastrace span by a [`FastraceCompatLayer`].
+///
+/// # Example
+///
+/// ```
+/// let span = tracing::info_span!("request");
+/// fastrace_tracing::with_fastrace_span(&span, |fastrace_span| {
+///     fastrace_span.add_property(|| ("cache.hit", "true"));
+/// });
+/// ```
+pub fn with_fastrace_span<R>(
+    span: &tracing::Span,
+    f: impl FnOnce(&mut fastrace::Span) -> R,
+) -> Option<R> {
+    with_span_extensions(span, |extensions| {
+        extensions.get_mut::<fastrace::Span>().map(f)
+    })
+    .flatten()
+}
+
+/// Runs `f` with the [`FastraceCompatLayer`] installed in the current default subscriber.
+///
+/// This gives access to the layer without keeping a handle to it from startup, to call the
+/// methods taking `&self`: [`FastraceCompatLayer::stats`], [`FastraceCompatLayer::evaluate`],
+/// and [`FastraceCompatLayer::set_shadow_mode`]. The rest of the configuration, e.g., filters,
+/// is fixed once the layer is built. `S` must be the subscriber the layer was added to, e.g.,
+/// [`Registry`](tracing_subscriber::Registry) if it was the first layer. Returns `None` if no
+/// such layer is installed. A [`SharedLayer`] can be looked up the same way, to keep a clone of
+/// it.
+///
+/// # Example
+///
+/// ```
+/// use fastrace_tracing::Decision;
+/// use fastrace_tracing::FastraceCompatLayer;
+/// use tracing_subscriber::Registry;
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let subscriber = Registry::default().with(FastraceCompatLayer::new());
+/// tracing::subscriber::set_global_default(subscriber).unwrap();
+///
+/// let span = tracing::info_span!("request");
+/// let decision =
+///     fastrace_tracing::with_installed_layer(|layer: &FastraceCompatLayer<Registry>| {
+///         layer.evaluate(span.metadata().unwrap())
+///     });
+/// assert_eq!(decision, Some(Decision::Record));
+///
+/// let stats = fastrace_tracing::with_installed_layer(|layer: &FastraceCompatLayer<Registry>| {
+///     layer.set_shadow_mode(true);
+///     layer.stats()
+/// });
+/// assert_eq!(stats.map(|stats| stats.spans), Some(1));
+/// ```
+pub fn with_installed_layer<S, R>(f: impl FnOnce(&FastraceCompatLayer<S>) -> R) -> Option<R>
+where
+    S: 'static,
+{
+    let mut f = Some(f);
+    tracing::dispatcher::get_default(|dispatch| {
+        let layer = dispatch.downcast_ref::<FastraceCompatLayer<S>>()?;
+        f.take().map(|f| f(layer))
+    })
+}
+
+/// Attaches an externally created fastrace span to `span`.
+///
+/// The layer uses `fastrace_span` for everything recorded on `span` from now on: fields recorded
+/// later, events, and the parent of child spans. The fastrace span the layer created for `span`
+/// is cancelled, so properties recorded on it so far are not reported.
+///
+/// This is useful for middleware that has already built a fastrace span, e.g., with a remote
+/// parent extracted from an incoming request. This has no effect if `span` is disabled or the
+/// [`FastraceCompatLayer`] is not installed.
+///
+/// # Example
+///
+/// ```
+/// use fastrace::prelude::SpanContext;
+///
+/// let parent = SpanContext::random();
+/// let span = tracing::info_span!("request");
+/// fastrace_tracing::set_fastrace_span(&span, fastrace::Span::root("request", parent));
+/// ```
+pub fn set_fastrace_span(span: &tracing::Span, fastrace_span: fastrace::Span) {
+    with_span_extensions(span, |extensions| {
+        if let Some(previous) = extensions.replace(fastrace_span) {
+            previous.cancel();
+        }
+    });
+}
+
+/// Runs `f` with the fastrace span bridged from the current tracing span.
+///
+/// See [`with_fastrace_span`] for details.
+///
+/// # Example
+///
+/// ```
+/// let span = tracing::info_span!("request");
+/// let _enter = span.enter();
+///
+/// fastrace_tracing::with_current_fastrace_span(|fastrace_span| {
+///     let _child = fastrace::Span::enter_with_parent("child", fastrace_span);
+/// });
+/// ```
+pub fn with_current_fastrace_span<R>(f: impl FnOnce(&mut fastrace::Span) -> R) -> Option<R> {
+    with_fastrace_span(&tracing::Span::current(), f)
+}
+
+/// Adds properties to the fastrace span bridged from the current tracing span.
+///
+/// This is meant for code that only knows about tracing, and, unlike
+/// [`fastrace::local::LocalSpan::add_properties`], does not depend on the fastrace local parent.
+/// The properties are recorded as is, like fastrace-native properties. Returns `false` if the
+/// current tracing span was not bridged to a fastrace span.
+///
+/// # Example
+///
+/// ```
+/// let span = tracing::info_span!("lookup");
+/// let _enter = span.enter();
+///
+/// fastrace_tracing::add_properties([("cache.hit", "true")]);
+/// ```
+pub fn add_properties<K, V>(properties: impl IntoIterator<Item = (K, V)>) -> bool
+where
+    K: Into<Cow<'static, str>>,
+    V: Into<Cow<'static, str>>,
+{
+    with_current_fastrace_span(|fastrace_span| {
+        fastrace_span.add_properties(|| properties);
+    })
+    .is_some()
+}
+
+/// Records the trace id and span id of the fastrace span bridged from `span` into its fields
+/// `trace_id_field` and `span_id_field`, as hex strings.
+///
+/// This lets logging layers that render span fields, e.g., `tracing_subscriber::fmt` with JSON
+/// output, carry the correlation ids of the bridged span. The fields must be declared when `span`
+/// is created, typically as [`field::Empty`]. Returns `false` if `span` was not bridged to a
+/// fastrace span, or was bridged to a no-op one.
+///
+/// # Example
+///
+/// ```
+/// let span = tracing::info_span!(
+///     "request",
+///     trace_id = tracing::field::Empty,
+///     span_id = tracing::field::Empty,
+/// );
+/// fastrace_tracing::record_span_context(&span, "trace_id", "span_id");
+/// ```
+pub fn record_span_context(
+    span: &tracing::Span,
+    trace_id_field: &str,
+    span_id_field: &str,
+) -> bool {
+    // The fields are recorded once the registry extensions are released, as recording them calls
+    // back into the layer.
+    let Some(context) =
+        with_fastrace_span(span, |fastrace_span| SpanContext::from_span(fastrace_span)).flatten()
+    else {
+        return false;
+    };
+    span.record(trace_id_field, format!("{:032x}", context.trace_id.0));
+    span.record(span_id_field, format!("{:016x}", context.span_id.0));
+    true
+}
+
+/// Returns `true` if values recorded on `span` are recorded by fastrace, i.e., `span` was bridged
+/// to a fastrace span that is neither a no-op nor cancelled.
+///
+/// This is a cheap check that lets applications skip computing expensive field values that would
+/// be discarded, e.g., because the trace was not sampled. Spans held back by
+/// [`FastraceCompatLayer::with_orphan_buffer`] or [`FastraceCompatLayer::with_early_buffer`]
+/// are recording.
+///
+/// # Example
+///
+/// ```
+/// let span = tracing::info_span!("query", plan = tracing::field::Empty);
+/// if fastrace_tracing::is_recording(&span) {
+///     span.record("plan", "full scan");
+/// }
+/// ```
+pub fn is_recording(span: &tracing::Span) -> bool {
+    with_span_extensions(span, |extensions| {
+        match extensions.get_mut::<fastrace::Span>() {
+            Some(fastrace_span) => {
+                SpanContext::from_span(fastrace_span).is_some_and(|context| context.sampled)
+            }
+            None => extensions.get_mut::<PendingSpan>().is_some(),
+        }
+    })
+    .unwrap_or(false)
+}
+
+/// Per-span counters of events folded by [`EventFolding`], keyed by level.
+#[derive(Default)]
+struct FoldedEvents {
+    counts: Vec<(Level, usize)>,
+}
+
+impl FoldedEvents {
+    /// Increments the counter for `level`, returning its previous value.
+    fn increment(&mut self, level: Level) -> usize {
+        match self.counts.iter_mut().find(|(l, _)| *l == level) {
+            Some((_, count)) => {
+                *count += 1;
+                *count - 1
+            }
+            None => {
+                self.counts.push((level, 1));
+                0
+            }
+        }
+    }
+}
+
+/// Per-span counters of child spans, for [`FastraceCompatLayer::with_max_children_per_span`].
+#[derive(Default)]
+struct ChildCount {
+    recorded: usize,
+    dropped: usize,
+}
+
+/// Marks a span recorded as an event on its nearest recorded ancestor.
+struct EventOnlySpan;
+
+/// Marks a span whose bridged fastrace span has ended before the tracing span closed.
+struct EndedSpan;
+
+/// The depth of a bridged span among bridged spans, for [`FastraceCompatLayer::with_max_depth`].
+struct SpanDepth(usize);
+
+/// Marks a bridged span of a trace started by the layer, whose sampling was decided by the
+/// [`AdaptiveSampler`].
+struct SampledTrace;
+
+/// Errors recorded within a span, for [`FastraceCompatLayer::with_error_summary`].
+#[derive(Default)]
+struct SpanErrors {
+    message: Option<String>,
+    events: usize,
+}
+
+/// Finds a `fastrace.as_span = true` field among the fields of an event.
+struct AsSpanFinder {
+    as_span: bool,
+}
+
+impl field::Visit for AsSpanFinder {
+    fn record_bool(&mut self, field: &field::Field, value: bool) {
+        if field.name() == FIELD_FASTRACE_AS_SPAN {
+            self.as_span = value;
+        }
+    }
+
+    fn record_debug(&mut self, _field: &field::Field, _value: &dyn fmt::Debug) {}
+}
+
+/// Collects the indices of the fields that have a value, as opposed to being declared with
+/// `field::Empty`.
+struct RecordedFields(Vec<usize>);
+
+impl field::Visit for RecordedFields {
+    fn record_debug(&mut self, field: &field::Field, _value: &dyn fmt::Debug) {
+        self.0.push(field.index());
+    }
+}
+
 struct EventNameFinder {
     name: Option<Cow<'static, str>>,
 }
@@ -110,19 +674,54 @@ impl field::Visit for EventNameFinder {
     }
 }
 
+/// Collects event fields as properties, to be recorded on the fastrace event by the caller.
 struct EventVisitor<'a> {
-    fastrace_event: &'a mut fastrace::Event,
+    properties: &'a mut Vec<Property>,
+    options: FieldOptions,
+}
+
+impl EventVisitor<'_> {
+    /// Returns the property key of `field`.
+    fn key(&self, field: &field::Field) -> &'static str {
+        self.options
+            .key_case
+            .map_or(field.name(), |key_case| key_case.convert(field.name()))
+    }
+
+    fn push(&mut self, key: impl Into<Cow<'static, str>>, value: impl Into<Cow<'static, str>>) {
+        self.properties.push((key.into(), value.into()));
+    }
+
+    /// Records the value of `field` as a property.
+    fn push_value(&mut self, field: &field::Field, value: FieldValue<'_>) {
+        let value = self.options.encode(value);
+        self.push(self.key(field), value);
+    }
+
+    /// Records a `timestamp` or `event.time_unix_nano` field holding nanoseconds since the Unix
+    /// epoch as the `event.time_unix_ns` property, see [`BackdatingReporter`].
+    ///
+    /// Returns `false` if the field is not a timestamp field or its value is not a valid
+    /// timestamp, in which case the field should be recorded as an ordinary property.
+    fn record_timestamp(&mut self, field: &field::Field, value: Option<u64>) -> bool {
+        if !matches!(field.name(), FIELD_TIMESTAMP | FIELD_EVENT_TIME_UNIX_NANO) {
+            return false;
+        }
+        let Some(value) = value else {
+            return false;
+        };
+        self.push(PROPERTY_EVENT_TIME, value.to_string());
+        true
+    }
 }
 
 impl field::Visit for EventVisitor<'_> {
     fn record_bool(&mut self, field: &field::Field, value: bool) {
-        if field.name() == "message" {
+        if matches!(field.name(), "message" | FIELD_FASTRACE_AS_SPAN) {
             return;
         }
 
-        take_mut::take(self.fastrace_event, |event| {
-            event.with_property(|| (field.name(), value.to_string()))
-        });
+        self.push_value(field, FieldValue::Bool(value));
     }
 
     fn record_f64(&mut self, field: &field::Field, value: f64) {
@@ -130,39 +729,62 @@ impl field::Visit for EventVisitor<'_> {
             return;
         }
 
-        take_mut::take(self.fastrace_event, |event| {
-            event.with_property(|| (field.name(), value.to_string()))
-        });
+        self.push_value(field, FieldValue::F64(value));
     }
 
     fn record_i64(&mut self, field: &field::Field, value: i64) {
         if field.name() == "message" {
             return;
         }
+        if self.record_timestamp(field, u64::try_from(value).ok()) {
+            return;
+        }
 
-        take_mut::take(self.fastrace_event, |event| {
-            event.with_property(|| (field.name(), value.to_string()))
-        });
+        self.push_value(field, FieldValue::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &field::Field, value: u64) {
+        if field.name() == "message" {
+            return;
+        }
+        if self.record_timestamp(field, Some(value)) {
+            return;
+        }
+
+        self.push_value(field, FieldValue::U64(value));
     }
 
     fn record_str(&mut self, field: &field::Field, value: &str) {
         if field.name() == "message" {
             return;
         }
+        if self.record_timestamp(field, value.parse().ok()) {
+            return;
+        }
 
-        take_mut::take(self.fastrace_event, |event| {
-            event.with_property(|| (field.name(), value.to_string()))
-        });
+        self.push_value(field, FieldValue::Str(value));
     }
 
     fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
         if field.name() == "message" {
             return;
         }
+        let value = format!("{:?}", value);
+        if let Some(max) = self.options.max_list_elements {
+            if flatten_list(self.properties, self.key(field), &value, max) {
+                return;
+            }
+        }
 
-        take_mut::take(self.fastrace_event, |event| {
-            event.with_property(|| (field.name(), format!("{:?}", value)))
-        });
+        self.push_value(field, FieldValue::Str(&value));
+    }
+
+    fn record_bytes(&mut self, field: &field::Field, value: &[u8]) {
+        if field.name() == "message" {
+            return;
+        }
+
+        self.push_value(field, FieldValue::Bytes(value));
     }
 
     fn record_error(
@@ -174,161 +796,2208 @@ impl field::Visit for EventVisitor<'_> {
             return;
         }
 
-        let mut chain: Vec<String> = Vec::new();
-        let mut next_err = value.source();
+        push_error(self.properties, self.key(field), value, self.options);
+    }
+}
 
-        while let Some(err) = next_err {
-            chain.push(err.to_string());
-            next_err = err.source();
-        }
+/// A property recorded on a fastrace span.
+type Property = (Cow<'static, str>, Cow<'static, str>);
+
+/// Records the error `value` of the field `key`, along with its chain of causes.
+fn push_error(
+    properties: &mut Vec<Property>,
+    key: &'static str,
+    value: &(dyn std::error::Error + 'static),
+    options: FieldOptions,
+) {
+    let mut chain: Vec<String> = Vec::new();
+    let mut next_err = value.source();
+
+    while let Some(err) = next_err {
+        chain.push(err.to_string());
+        next_err = err.source();
+    }
 
-        let error_msg = value.to_string();
+    let error_msg = value.to_string();
 
-        take_mut::take(self.fastrace_event, |event| {
-            event.with_property(|| (field.name(), error_msg.to_string()))
-        });
-        take_mut::take(self.fastrace_event, |event| {
-            event.with_property(|| (FIELD_EXCEPTION_MESSAGE, error_msg.to_string()))
-        });
-        take_mut::take(self.fastrace_event, |event| {
-            event.with_property(|| (format!("{}.chain", field.name()), format!("{:?}", chain)))
-        });
-        take_mut::take(self.fastrace_event, |event| {
-            event.with_property(|| (FIELD_EXCEPTION_STACKTRACE, format!("{:?}", chain)))
-        });
+    properties.push((key.into(), error_msg.clone().into()));
+    properties.push((FIELD_EXCEPTION_MESSAGE.into(), error_msg.into()));
+    let stacktrace = options.stacktrace_format.render(&chain);
+    match options.max_error_causes {
+        Some(max) => {
+            for (i, cause) in chain.into_iter().take(max).enumerate() {
+                properties.push((format!("{}.cause.{}", key, i).into(), cause.into()));
+            }
+        }
+        None => {
+            properties.push((format!("{}.chain", key).into(), stacktrace.clone().into()));
+        }
     }
+    properties.push((FIELD_EXCEPTION_STACKTRACE.into(), stacktrace.into()));
+}
+
+/// A predicate on the name and value of a span field, see
+/// [`FastraceCompatLayer::with_span_drop_if`].
+type DropIf = dyn Fn(&str, &str) -> bool + Send + Sync;
+
+/// Span fields that control how the layer bridges the span, rather than being recorded.
+#[derive(Default)]
+struct ControlFields {
+    root: bool,
+    skip: bool,
+    /// Whether a field matched the predicate configured with
+    /// [`FastraceCompatLayer::with_span_drop_if`].
+    dropped: bool,
+    event_only: bool,
+    name: Option<String>,
+    sample_rate: Option<f64>,
 }
 
+/// Collects span fields as properties, to be recorded on the fastrace span by the caller.
 struct SpanAttributeVisitor<'a> {
-    fastrace_span: &'a mut fastrace::Span,
+    properties: &'a mut Vec<Property>,
+    baggage: &'a mut Baggage,
+    control: &'a mut ControlFields,
+    lazy: Option<&'a mut LazyFields>,
+    drop_if: Option<&'a DropIf>,
+    options: FieldOptions,
 }
 
-impl field::Visit for SpanAttributeVisitor<'_> {
-    fn record_bool(&mut self, field: &field::Field, value: bool) {
-        take_mut::take(self.fastrace_span, |span| {
-            span.with_property(|| (field.name(), value.to_string()))
-        });
+impl SpanAttributeVisitor<'_> {
+    /// Returns the property key of `field`.
+    fn key(&self, field: &field::Field) -> &'static str {
+        self.options
+            .key_case
+            .map_or(field.name(), |key_case| key_case.convert(field.name()))
     }
 
-    fn record_f64(&mut self, field: &field::Field, value: f64) {
-        take_mut::take(self.fastrace_span, |span| {
-            span.with_property(|| (field.name(), value.to_string()))
-        });
+    fn push(&mut self, key: impl Into<Cow<'static, str>>, value: impl Into<Cow<'static, str>>) {
+        self.properties.push((key.into(), value.into()));
     }
 
-    fn record_i64(&mut self, field: &field::Field, value: i64) {
-        take_mut::take(self.fastrace_span, |span| {
-            span.with_property(|| (field.name(), value.to_string()))
-        });
+    /// Records the value of `field` as a property.
+    fn push_value(&mut self, field: &field::Field, value: FieldValue<'_>) {
+        let value = self.options.encode(value);
+        self.push(self.key(field), value);
     }
 
-    fn record_str(&mut self, field: &field::Field, value: &str) {
-        take_mut::take(self.fastrace_span, |span| {
-            span.with_property(|| (field.name(), value.to_string()))
-        });
+    /// Marks the span as dropped if `field` matches the predicate configured with
+    /// [`FastraceCompatLayer::with_span_drop_if`].
+    fn check_drop_if(&mut self, field: &field::Field, value: FieldValue<'_>) {
+        let Some(drop_if) = self.drop_if else {
+            return;
+        };
+        let matches = match value {
+            FieldValue::Str(value) => drop_if(field.name(), value),
+            value => drop_if(field.name(), &value.encode()),
+        };
+        self.control.dropped |= matches;
     }
 
-    fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
-        take_mut::take(self.fastrace_span, |span| {
-            span.with_property(|| (field.name(), format!("{:?}", value)))
-        });
+    /// Collects a `baggage.*` field into the baggage of the span.
+    ///
+    /// Returns `false` if the field is not a baggage field. Baggage fields are not recorded as
+    /// ordinary properties; the caller stamps the collected baggage onto the span instead.
+    fn record_baggage(&mut self, field: &field::Field, value: impl FnOnce() -> String) -> bool {
+        let Some(key) = field.name().strip_prefix(FIELD_BAGGAGE_PREFIX) else {
+            return false;
+        };
+        self.baggage.insert(key, value());
+        true
     }
 
-    fn record_error(
-        &mut self,
-        field: &tracing_core::Field,
-        value: &(dyn std::error::Error + 'static),
-    ) {
-        let mut chain: Vec<String> = Vec::new();
-        let mut next_err = value.source();
-
-        while let Some(err) = next_err {
-            chain.push(err.to_string());
-            next_err = err.source();
+    /// Records a `links` field as structured `link.N.trace_id`/`link.N.span_id` properties.
+    ///
+    /// Returns `false` if the value does not contain any valid W3C traceparent, B3, or X-Ray
+    /// value, in which case the field should be recorded as an ordinary property.
+    fn record_links(&mut self, value: &str) -> bool {
+        let links = value
+            .split(',')
+            .filter_map(|link| {
+                let link = link.trim();
+                SpanContext::decode_w3c_traceparent(link)
+                    .or_else(|| B3::decode(link))
+                    .or_else(|| XRay::decode(link))
+            })
+            .collect::<Vec<_>>();
+        if links.is_empty() {
+            return false;
         }
 
-        let error_msg = value.to_string();
-
-        take_mut::take(self.fastrace_span, |span| {
-            span.with_property(|| (field.name(), error_msg.to_string()))
-        });
-        take_mut::take(self.fastrace_span, |span| {
-            span.with_property(|| (FIELD_EXCEPTION_MESSAGE, error_msg.to_string()))
-        });
-        take_mut::take(self.fastrace_span, |span| {
-            span.with_property(|| (format!("{}.chain", field.name()), format!("{:?}", chain)))
-        });
-        take_mut::take(self.fastrace_span, |span| {
-            span.with_property(|| (FIELD_EXCEPTION_STACKTRACE, format!("{:?}", chain)))
-        });
+        for (i, link) in links.iter().enumerate() {
+            self.push(
+                format!("link.{}.trace_id", i),
+                format!("{:032x}", link.trace_id.0),
+            );
+            self.push(
+                format!("link.{}.span_id", i),
+                format!("{:016x}", link.span_id.0),
+            );
+        }
+        true
     }
 }
 
-impl<S> FastraceCompatLayer<S>
-where
-    S: Subscriber + for<'span> LookupSpan<'span>,
-{
-    /// Creates a new [`FastraceCompatLayer`] with default settings.
-    pub fn new() -> Self {
-        FastraceCompatLayer {
-            location: true,
-            with_threads: true,
-            with_level: false,
-            _phantom: marker::PhantomData,
+impl field::Visit for SpanAttributeVisitor<'_> {
+    fn record_bool(&mut self, field: &field::Field, value: bool) {
+        if self.record_baggage(field, || value.to_string()) {
+            return;
+        }
+        match field.name() {
+            FIELD_FASTRACE_ROOT => {
+                self.control.root = value;
+                return;
+            }
+            FIELD_FASTRACE_SKIP => {
+                self.control.skip = value;
+                return;
+            }
+            FIELD_FASTRACE_EVENT_ONLY => {
+                self.control.event_only = value;
+                return;
+            }
+            _ => {}
+        }
+        self.check_drop_if(field, FieldValue::Bool(value));
+        if let Some(lazy) = &mut self.lazy {
+            lazy.push(field, RawValue::Bool(value));
+            return;
         }
-    }
 
-    /// Configures whether source code location information is included in spans.
-    ///
-    /// When enabled, span properties will include:
-    /// - `code.filepath`: The file where the span was created
-    /// - `code.namespace`: The module path where the span was created
-    /// - `code.lineno`: The line number where the span was created
-    ///
-    /// Default is `true`.
-    pub fn with_location(self, location: bool) -> Self {
-        Self { location, ..self }
+        self.push_value(field, FieldValue::Bool(value));
     }
 
-    /// Configures whether thread information is included in spans.
-    ///
-    /// When enabled, span properties will include:
-    /// - `thread.id`: The numeric ID of the thread
-    /// - `thread.name`: The name of the thread (if available)
-    ///
-    /// Default is `true`.
-    pub fn with_threads(self, threads: bool) -> Self {
-        Self {
-            with_threads: threads,
-            ..self
+    fn record_f64(&mut self, field: &field::Field, value: f64) {
+        if self.record_baggage(field, || value.to_string()) {
+            return;
         }
-    }
-
-    /// Configures whether level information is included in span properties.
-    ///
-    /// When enabled, spans will include the tracing level (trace, debug, info, etc.)
-    /// as a property named `level`.
-    ///
-    /// Default is `false`.
-    pub fn with_level(self, level: bool) -> Self {
-        Self {
-            with_level: level,
-            ..self
+        if field.name() == FIELD_SAMPLE_RATE {
+            self.control.sample_rate = Some(value);
+            return;
+        }
+        self.check_drop_if(field, FieldValue::F64(value));
+        if let Some(lazy) = &mut self.lazy {
+            lazy.push(field, RawValue::F64(value));
+            return;
         }
+
+        self.push_value(field, FieldValue::F64(value));
     }
 
-    fn new_fastrace_span(&self, attrs: &Attributes<'_>, ctx: &Context<'_, S>) -> fastrace::Span {
-        if let Some(parent) = attrs.parent() {
-            // A span can have an _explicit_ parent that is NOT seen by this `Layer` (for which
+    fn record_i64(&mut self, field: &field::Field, value: i64) {
+        if self.record_baggage(field, || value.to_string()) {
+            return;
+        }
+        if field.name() == FIELD_SAMPLE_RATE {
+            self.control.sample_rate = Some(value as f64);
+            return;
+        }
+        self.check_drop_if(field, FieldValue::I64(value));
+        if let Some(lazy) = &mut self.lazy {
+            lazy.push(field, RawValue::I64(value));
+            return;
+        }
+
+        self.push_value(field, FieldValue::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &field::Field, value: u64) {
+        if self.record_baggage(field, || value.to_string()) {
+            return;
+        }
+        if field.name() == FIELD_SAMPLE_RATE {
+            self.control.sample_rate = Some(value as f64);
+            return;
+        }
+        self.check_drop_if(field, FieldValue::U64(value));
+        if let Some(lazy) = &mut self.lazy {
+            lazy.push(field, RawValue::U64(value));
+            return;
+        }
+
+        self.push_value(field, FieldValue::U64(value));
+    }
+
+    fn record_str(&mut self, field: &field::Field, value: &str) {
+        if self.record_baggage(field, || value.to_string()) {
+            return;
+        }
+        if field.name() == FIELD_FASTRACE_NAME {
+            self.control.name = Some(value.to_string());
+            return;
+        }
+        if field.name() == FIELD_LINKS && self.record_links(value) {
+            return;
+        }
+        self.check_drop_if(field, FieldValue::Str(value));
+        if let Some(lazy) = &mut self.lazy {
+            lazy.push(field, RawValue::Str(value.to_string()));
+            return;
+        }
+
+        self.push_value(field, FieldValue::Str(value));
+    }
+
+    fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
+        let value = format!("{:?}", value);
+        if self.record_baggage(field, || value.clone()) {
+            return;
+        }
+        if field.name() == FIELD_FASTRACE_NAME {
+            self.control.name = Some(value);
+            return;
+        }
+        if field.name() == FIELD_LINKS && self.record_links(&value) {
+            return;
+        }
+        self.check_drop_if(field, FieldValue::Str(&value));
+        if let Some(lazy) = &mut self.lazy {
+            lazy.push(field, RawValue::Debug(value));
+            return;
+        }
+        if let Some(max) = self.options.max_list_elements {
+            if flatten_list(self.properties, self.key(field), &value, max) {
+                return;
+            }
+        }
+
+        self.push_value(field, FieldValue::Str(&value));
+    }
+
+    fn record_bytes(&mut self, field: &field::Field, value: &[u8]) {
+        if self.record_baggage(field, || FieldValue::Bytes(value).encode()) {
+            return;
+        }
+        self.check_drop_if(field, FieldValue::Bytes(value));
+        if let Some(lazy) = &mut self.lazy {
+            lazy.push(field, RawValue::Bytes(value.to_vec()));
+            return;
+        }
+
+        self.push_value(field, FieldValue::Bytes(value));
+    }
+
+    fn record_error(
+        &mut self,
+        field: &tracing_core::Field,
+        value: &(dyn std::error::Error + 'static),
+    ) {
+        if self.record_baggage(field, || value.to_string()) {
+            return;
+        }
+
+        push_error(self.properties, self.key(field), value, self.options);
+    }
+}
+
+impl<S> FastraceCompatLayer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    /// Creates a new [`FastraceCompatLayer`] with default settings.
+    pub fn new() -> Self {
+        FastraceCompatLayer {
+            span_properties: SpanProperties::default(),
+            with_tracing_ids: false,
+            field_options: FieldOptions::default(),
+            lazy_fields: false,
+            ignore_span_fields: false,
+            #[cfg(feature = "fmt")]
+            fields_formatter: None,
+            empty_field_placeholder: None,
+            sanitizer: None,
+            root_properties: None,
+            #[cfg(feature = "tracing-opentelemetry")]
+            otel_data: false,
+            filters: None,
+            span_actions: SpanActions::new(),
+            span_drop_if: None,
+            event_folding: None,
+            event_batcher: None,
+            local_parent_events: false,
+            error_summary: false,
+            close_summary: None,
+            events_as_spans: false,
+            event_to_span_bridge: None,
+            return_capture: None,
+            target_in_event_names: false,
+            span_name_on_events: false,
+            root_span_name_on_events: false,
+            orphan_buffer: None,
+            early_buffer: None,
+            #[cfg(feature = "log")]
+            log_fallback: false,
+            max_children_per_span: None,
+            max_depth: None,
+            spans_in_flight: None,
+            deadlines: None,
+            span_segmentation: None,
+            late_records: LateRecords::Event,
+            span_memory_budget: None,
+            max_properties_per_event: None,
+            #[cfg(feature = "shutdown")]
+            shutdown: None,
+            adaptive_sampler: None,
+            self_telemetry: None,
+            counters: Counters::default(),
+            shadow_mode: AtomicBool::new(false),
+            roots: None,
+            tenant_resolver: None,
+            get_context: WithContext(Self::get_context),
+            _phantom: marker::PhantomData,
+        }
+    }
+
+    /// Creates a [`FastraceCompatLayer`] recording as little as possible, for hot paths and
+    /// high-volume services.
+    ///
+    /// - No location, thread, or level properties
+    /// - Spans below `INFO` are recorded as events, see [`with_span_level_threshold`]
+    /// - Events below `INFO` are only counted, see [`with_event_folding`]
+    /// - At most 1,000 child spans per span, see [`with_max_children_per_span`]
+    ///
+    /// Every setting can be overridden with the usual builder methods.
+    ///
+    /// [`with_span_level_threshold`]: FastraceCompatLayer::with_span_level_threshold
+    /// [`with_event_folding`]: FastraceCompatLayer::with_event_folding
+    /// [`with_max_children_per_span`]: FastraceCompatLayer::with_max_children_per_span
+    pub fn minimal() -> Self {
+        Self::new()
+            .with_location(false)
+            .with_threads(false)
+            .with_level(false)
+            .with_span_level_threshold(Level::INFO)
+            .with_event_folding(Level::INFO, EventFolding::Count)
+            .with_max_children_per_span(1_000)
+    }
+
+    /// Creates a [`FastraceCompatLayer`] with a balance between detail and overhead suited to
+    /// most services.
+    ///
+    /// - Location and level properties, but no thread properties
+    /// - Spans of every level are recorded
+    /// - `TRACE` events are only counted, see [`with_event_folding`]
+    /// - At most 10,000 child spans per span, see [`with_max_children_per_span`]
+    ///
+    /// Every setting can be overridden with the usual builder methods.
+    ///
+    /// [`with_event_folding`]: FastraceCompatLayer::with_event_folding
+    /// [`with_max_children_per_span`]: FastraceCompatLayer::with_max_children_per_span
+    pub fn standard() -> Self {
+        Self::new()
+            .with_location(true)
+            .with_threads(false)
+            .with_level(true)
+            .with_event_folding(Level::DEBUG, EventFolding::Count)
+            .with_max_children_per_span(10_000)
+    }
+
+    /// Creates a [`FastraceCompatLayer`] recording everything, for debugging.
+    ///
+    /// - Location, thread, level, and tracing id properties
+    /// - Spans and events of every level are recorded
+    /// - No limit on the number of child spans
+    ///
+    /// Every setting can be overridden with the usual builder methods.
+    pub fn verbose() -> Self {
+        Self::new()
+            .with_location(true)
+            .with_threads(true)
+            .with_level(true)
+            .with_tracing_ids(true)
+    }
+
+    /// Configures whether source code location information is included in spans.
+    ///
+    /// When enabled, span properties will include:
+    /// - `code.filepath`: The file where the span was created
+    /// - `code.namespace`: The module path where the span was created
+    /// - `code.lineno`: The line number where the span was created
+    ///
+    /// This has no effect with the `strip-location` feature, which compiles the location code
+    /// out.
+    ///
+    /// Default is `true`.
+    pub fn with_location(self, location: bool) -> Self {
+        Self {
+            span_properties: self.span_properties.with_location(location),
+            ..self
+        }
+    }
+
+    /// Configures how the paths recorded in `code.filepath` properties are rendered, see
+    /// [`PathStyle`].
+    ///
+    /// Default is [`PathStyle::Absolute`].
+    pub fn with_path_style(self, path_style: PathStyle) -> Self {
+        Self {
+            span_properties: self.span_properties.with_path_style(path_style),
+            ..self
+        }
+    }
+
+    /// Configures a prefix removed from the paths recorded in `code.filepath` properties, before
+    /// applying the [`PathStyle`].
+    ///
+    /// Default is `None`.
+    pub fn with_path_prefix_stripped(self, prefix: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            span_properties: self.span_properties.with_path_prefix(prefix.into()),
+            ..self
+        }
+    }
+
+    /// Configures whether thread information is included in spans.
+    ///
+    /// When enabled, span properties will include:
+    /// - `thread.id`: The numeric ID of the thread
+    /// - `thread.name`: The name of the thread (if available)
+    ///
+    /// Default is `true`.
+    pub fn with_threads(self, threads: bool) -> Self {
+        Self {
+            span_properties: self.span_properties.with_threads(threads),
+            ..self
+        }
+    }
+
+    /// Configures whether level information is included in span properties.
+    ///
+    /// When enabled, spans will include the tracing level (trace, debug, info, etc.)
+    /// as a property named `level`.
+    ///
+    /// Default is `false`.
+    pub fn with_level(self, level: bool) -> Self {
+        Self {
+            span_properties: self.span_properties.with_level(level),
+            ..self
+        }
+    }
+
+    /// Configures whether the tracing span ids are included in span properties.
+    ///
+    /// When enabled, span properties will include:
+    /// - `tracing.span_id`: The id of the tracing span
+    /// - `tracing.parent_id`: The id of the parent tracing span (if any)
+    ///
+    /// Default is `false`.
+    pub fn with_tracing_ids(self, tracing_ids: bool) -> Self {
+        Self {
+            with_tracing_ids: tracing_ids,
+            ..self
+        }
+    }
+
+    /// Configures whether process information is included in the properties of root spans
+    /// created by the layer.
+    ///
+    /// When enabled, root span properties will include:
+    /// - `process.pid`: The id of the process
+    /// - `host.name`: The host name (if available)
+    /// - `service.version`: The version configured with [`with_service_version`], or else the
+    ///   `SERVICE_VERSION` environment variable (if available)
+    ///
+    /// Default is `false`.
+    ///
+    /// [`with_service_version`]: FastraceCompatLayer::with_service_version
+    pub fn with_process_info(self, process_info: bool) -> Self {
+        let root_properties = self.root_properties.unwrap_or_default();
+        Self {
+            root_properties: Some(Box::new(root_properties.with_process_info(process_info))),
+            ..self
+        }
+    }
+
+    /// Configures the `service.version` property recorded by [`with_process_info`].
+    ///
+    /// Default is `None`.
+    ///
+    /// [`with_process_info`]: FastraceCompatLayer::with_process_info
+    pub fn with_service_version(self, version: impl Into<String>) -> Self {
+        let root_properties = self.root_properties.unwrap_or_default();
+        Self {
+            root_properties: Some(Box::new(
+                root_properties.with_service_version(version.into()),
+            )),
+            ..self
+        }
+    }
+
+    /// Configures whether Kubernetes information is included in the properties of root spans
+    /// created by the layer. Requires the `k8s` feature.
+    ///
+    /// When enabled, root span properties will include:
+    /// - `k8s.pod.name`: From `K8S_POD_NAME` or `POD_NAME`
+    /// - `k8s.namespace.name`: From `K8S_NAMESPACE_NAME` or `POD_NAMESPACE`
+    /// - `k8s.node.name`: From `K8S_NODE_NAME` or `NODE_NAME`
+    ///
+    /// Default is `false`.
+    #[cfg(feature = "k8s")]
+    pub fn with_k8s_info(self, k8s_info: bool) -> Self {
+        let root_properties = self.root_properties.unwrap_or_default();
+        Self {
+            root_properties: Some(Box::new(root_properties.with_k8s_info(k8s_info))),
+            ..self
+        }
+    }
+
+    /// Configures whether the name and attributes that `tracing-opentelemetry` recorded for a
+    /// span are reused for the bridged span. Requires the `tracing-opentelemetry` feature.
+    ///
+    /// The `OpenTelemetryLayer` must be added to the subscriber before this layer.
+    ///
+    /// Default is `false`.
+    #[cfg(feature = "tracing-opentelemetry")]
+    pub fn with_otel_data(self, otel_data: bool) -> Self {
+        Self { otel_data, ..self }
+    }
+
+    /// Configures the naming convention the names of span and event fields are converted to,
+    /// see [`KeyCase`].
+    ///
+    /// Default is `None`, which records field names as is.
+    pub fn with_key_case(self, key_case: KeyCase) -> Self {
+        Self {
+            field_options: FieldOptions {
+                key_case: Some(key_case),
+                ..self.field_options
+            },
+            ..self
+        }
+    }
+
+    /// Configures fields whose Debug output is a list to be recorded as one property per
+    /// element, named `<field>.0`, `<field>.1`, and so on, up to `max_elements` elements.
+    ///
+    /// Default is `None`, which records lists as a single property.
+    pub fn with_list_flattening(self, max_elements: usize) -> Self {
+        Self {
+            field_options: FieldOptions {
+                max_list_elements: Some(max_elements),
+                ..self.field_options
+            },
+            ..self
+        }
+    }
+
+    /// Configures fields declared with [`field::Empty`] to be recorded as properties holding
+    /// `placeholder` until they have a value.
+    ///
+    /// Default is `None`, which only records fields once they have a value.
+    pub fn with_empty_field_placeholder(self, placeholder: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            empty_field_placeholder: Some(placeholder.into()),
+            ..self
+        }
+    }
+
+    /// Configures the causes of recorded errors to be recorded as one property per cause, named
+    /// `<field>.cause.0`, `<field>.cause.1`, and so on, up to `max_depth` causes.
+    ///
+    /// Default is `None`, which records the causes as a single `<field>.chain` property.
+    pub fn with_indexed_error_causes(self, max_depth: usize) -> Self {
+        Self {
+            field_options: FieldOptions {
+                max_error_causes: Some(max_depth),
+                ..self.field_options
+            },
+            ..self
+        }
+    }
+
+    /// Configures how the causes of recorded errors are rendered in the `exception.stacktrace`
+    /// and `<field>.chain` properties, see [`StacktraceFormat`].
+    ///
+    /// Default is [`StacktraceFormat::Debug`].
+    pub fn with_stacktrace_format(self, format: StacktraceFormat) -> Self {
+        Self {
+            field_options: FieldOptions {
+                stacktrace_format: format,
+                ..self.field_options
+            },
+            ..self
+        }
+    }
+
+    /// Configures how field values are converted to property values, see [`FieldValue`].
+    ///
+    /// Default is `None`, which uses [`FieldValue::encode`].
+    pub fn with_value_encoder(self, encoder: fn(&FieldValue<'_>) -> String) -> Self {
+        Self {
+            field_options: FieldOptions {
+                value_encoder: Some(encoder),
+                ..self.field_options
+            },
+            ..self
+        }
+    }
+
+    /// Configures a [`FormatFields`](tracing_subscriber::fmt::FormatFields) implementation
+    /// rendering the fields of spans and events into a `tracing.fields` property, e.g., the
+    /// formatter of a co-installed `fmt` layer. Requires the `fmt` feature.
+    ///
+    /// Default is `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    /// use tracing_subscriber::fmt::format::DefaultFields;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+    ///     .with_fields_formatter(DefaultFields::new());
+    /// ```
+    #[cfg(feature = "fmt")]
+    pub fn with_fields_formatter<N>(self, formatter: N) -> Self
+    where
+        N: for<'writer> tracing_subscriber::fmt::FormatFields<'writer> + Send + Sync + 'static,
+    {
+        Self {
+            fields_formatter: Some(Box::new(formatter)),
+            ..self
+        }
+    }
+
+    /// Configures whether the conversion of span fields to properties is deferred until the span
+    /// records its first event or closes, so that cancelled spans never pay for it.
+    ///
+    /// Default is `false`.
+    pub fn with_lazy_fields(self, lazy_fields: bool) -> Self {
+        Self {
+            lazy_fields,
+            ..self
+        }
+    }
+
+    /// Configures whether the fields of spans are ignored, including the fields interpreted by
+    /// the layer. The fields of events are still recorded.
+    ///
+    /// Default is `false`.
+    pub fn with_ignore_span_fields(self, ignore_span_fields: bool) -> Self {
+        Self {
+            ignore_span_fields,
+            ..self
+        }
+    }
+
+    /// Configures whether ANSI escape sequences are removed from event names and property
+    /// values.
+    ///
+    /// Default is `false`.
+    pub fn with_ansi_stripping(self, strip_ansi: bool) -> Self {
+        let sanitizer = self.sanitizer.unwrap_or_default();
+        Self {
+            sanitizer: Some(Box::new(sanitizer.with_ansi_stripping(strip_ansi))),
+            ..self
+        }
+    }
+
+    /// Configures how control characters in event names and property values are rewritten, see
+    /// [`ControlCharacters`].
+    ///
+    /// Default is `None`, which forwards control characters as is.
+    pub fn with_control_characters(self, control_characters: ControlCharacters) -> Self {
+        let sanitizer = self.sanitizer.unwrap_or_default();
+        Self {
+            sanitizer: Some(Box::new(
+                sanitizer.with_control_characters(control_characters),
+            )),
+            ..self
+        }
+    }
+
+    /// Configures the [`FieldPolicy`] deciding which fields are recorded, based on their
+    /// [`Classification`].
+    ///
+    /// Default is `None`, which records every field.
+    pub fn with_field_policy(self, policy: FieldPolicy) -> Self {
+        let sanitizer = self.sanitizer.unwrap_or_default();
+        Self {
+            sanitizer: Some(Box::new(sanitizer.with_field_policy(policy))),
+            ..self
+        }
+    }
+
+    /// Configures the values of `fields` to be replaced with a hash salted with `salt`.
+    ///
+    /// Default is `None`, which records every value as is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    ///
+    /// let salt = std::env::var("TRACE_SALT").unwrap_or_default();
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+    ///     .with_hashed_fields(["user.email", "ip"], salt);
+    /// ```
+    pub fn with_hashed_fields(
+        self,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+        salt: impl AsRef<[u8]>,
+    ) -> Self {
+        let fields = fields.into_iter().map(Into::into).collect();
+        let sanitizer = self.sanitizer.unwrap_or_default();
+        Self {
+            sanitizer: Some(Box::new(
+                sanitizer.with_field_hasher(FieldHasher::new(fields, salt.as_ref())),
+            )),
+            ..self
+        }
+    }
+
+    /// Configures the most verbose level of the spans and events recorded for each target and
+    /// its submodules, the longest matching target winning.
+    ///
+    /// Default is `None`, which records spans and events of every level.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    /// use tracing::Level;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+    ///     .with_target_levels([("sqlx", Level::DEBUG), ("", Level::INFO)]);
+    /// ```
+    pub fn with_target_levels(
+        self,
+        levels: impl IntoIterator<Item = (impl Into<Cow<'static, str>>, Level)>,
+    ) -> Self {
+        let levels = levels
+            .into_iter()
+            .map(|(target, level)| (target.into(), level))
+            .collect();
+        let filters = self.filters.unwrap_or_default();
+        Self {
+            filters: Some(Box::new(
+                filters.with_target_levels(TargetLevels::new(levels)),
+            )),
+            ..self
+        }
+    }
+
+    /// Configures the minimum level at which tracing spans are materialized as fastrace spans.
+    ///
+    /// Spans below the threshold are recorded as events on their nearest recorded ancestor, as
+    /// with [`SpanAction::EventOnly`].
+    ///
+    /// Default is `None`, which materializes spans of every level.
+    pub fn with_span_level_threshold(self, level: Level) -> Self {
+        let filters = self.filters.unwrap_or_default();
+        Self {
+            filters: Some(Box::new(filters.with_span_level_threshold(level))),
+            ..self
+        }
+    }
+
+    /// Configures a policy deciding, once per callsite, how tracing spans are bridged.
+    ///
+    /// Default is `None`, which records every span.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    /// use fastrace_tracing::SpanAction;
+    ///
+    /// let layer =
+    ///     FastraceCompatLayer::<tracing_subscriber::Registry>::new().with_span_policy(|meta| {
+    ///         match meta.target() {
+    ///             target if target.starts_with("h2") => SpanAction::Discard,
+    ///             target if target.starts_with("hyper") => SpanAction::EventOnly,
+    ///             _ => SpanAction::Record,
+    ///         }
+    ///     });
+    /// ```
+    pub fn with_span_policy(
+        self,
+        policy: impl Fn(&Metadata<'_>) -> SpanAction + Send + Sync + 'static,
+    ) -> Self {
+        let filters = self.filters.unwrap_or_default();
+        Self {
+            filters: Some(Box::new(filters.with_span_policy(SpanPolicy::new(policy)))),
+            ..self
+        }
+    }
+
+    /// Configures a predicate on the name and value of the fields of new spans. Spans for which
+    /// it returns `true` are bridged to noop fastrace spans, along with their descendants.
+    ///
+    /// Default is `None`, which drops no spans based on their fields.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+    ///     .with_span_drop_if(|field, value| field == "http.target" && value == "/healthz");
+    /// ```
+    pub fn with_span_drop_if(
+        self,
+        predicate: impl Fn(&str, &str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            span_drop_if: Some(Box::new(predicate)),
+            ..self
+        }
+    }
+
+    /// Installs a [`NoiseFilter`] demoting or dropping the spans and events of noisy targets,
+    /// typically one of the [`Presets`]. Installing several filters merges their rules.
+    ///
+    /// Default is `None`, which records every span and event.
+    pub fn with_noise_filter(self, filter: NoiseFilter) -> Self {
+        let filters = self.filters.unwrap_or_default();
+        Self {
+            filters: Some(Box::new(filters.with_noise_filter(filter))),
+            ..self
+        }
+    }
+
+    /// Configures the targets of the fastrace reporter and the libraries it uses, whose spans
+    /// and events are never bridged.
+    ///
+    /// Default is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+    ///     .with_reporter_targets(["reqwest", "hyper"]);
+    /// ```
+    pub fn with_reporter_targets(
+        self,
+        targets: impl IntoIterator<Item = impl Into<Cow<'static, str>>>,
+    ) -> Self {
+        let targets = targets.into_iter().map(Into::into).collect();
+        let filters = self.filters.unwrap_or_default();
+        Self {
+            filters: Some(Box::new(filters.with_reporter_targets(targets))),
+            ..self
+        }
+    }
+
+    /// Configures module paths whose spans and events, including those of their submodules, are
+    /// not bridged.
+    ///
+    /// Default is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+    ///     .with_excluded_module_paths(["my_crate::internal::"]);
+    /// ```
+    pub fn with_excluded_module_paths(
+        self,
+        paths: impl IntoIterator<Item = impl Into<Cow<'static, str>>>,
+    ) -> Self {
+        let paths = paths.into_iter().map(Into::into).collect();
+        let filters = self.filters.unwrap_or_default();
+        Self {
+            filters: Some(Box::new(filters.with_excluded_module_paths(paths))),
+            ..self
+        }
+    }
+
+    /// Configures the spans of `targets` and their submodules to be recorded as events, see
+    /// [`SpanAction::EventOnly`].
+    ///
+    /// This installs a [`NoiseFilter`], see [`with_noise_filter`].
+    ///
+    /// [`with_noise_filter`]: FastraceCompatLayer::with_noise_filter
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+    ///     .with_event_only_targets(["reqwest"]);
+    /// ```
+    pub fn with_event_only_targets(
+        self,
+        targets: impl IntoIterator<Item = impl Into<Cow<'static, str>>>,
+    ) -> Self {
+        let filter = targets
+            .into_iter()
+            .fold(NoiseFilter::new(), |filter, target| {
+                filter.with_rule(target, SpanAction::EventOnly, Level::TRACE)
+            });
+        self.with_noise_filter(filter)
+    }
+
+    /// Configures events with a level below `threshold` to be folded into their span, see
+    /// [`EventFolding`].
+    ///
+    /// Default is `None`, which records events of every level as fastrace events.
+    pub fn with_event_folding(self, threshold: Level, folding: EventFolding) -> Self {
+        Self {
+            event_folding: Some((threshold, folding)),
+            ..self
+        }
+    }
+
+    /// Installs an [`EventToSpanBridge`] synthesizing spans from pairs of start and end events.
+    ///
+    /// Default is `None`.
+    pub fn with_event_to_span_bridge(self, bridge: EventToSpanBridge) -> Self {
+        Self {
+            event_to_span_bridge: Some(Box::new(bridge)),
+            ..self
+        }
+    }
+
+    /// Configures whether events are recorded as zero-duration child spans of their span,
+    /// rather than as span events.
+    ///
+    /// Default is `false`.
+    pub fn with_events_as_spans(self, events_as_spans: bool) -> Self {
+        Self {
+            events_as_spans,
+            ..self
+        }
+    }
+
+    /// Configures whether event names are prefixed with the target of the event, as in
+    /// `"{target}: {message}"`.
+    ///
+    /// Default is `false`.
+    pub fn with_target_in_event_names(self, target_in_event_names: bool) -> Self {
+        Self {
+            target_in_event_names,
+            ..self
+        }
+    }
+
+    /// Configures whether events carry the name of their tracing span, as a `span.name`
+    /// property.
+    ///
+    /// Default is `false`.
+    pub fn with_span_name_on_events(self, span_name_on_events: bool) -> Self {
+        Self {
+            span_name_on_events,
+            ..self
+        }
+    }
+
+    /// Configures whether events carry the name of their outermost tracing span, as a
+    /// `span.root_name` property.
+    ///
+    /// Default is `false`.
+    pub fn with_root_span_name_on_events(self, root_span_name_on_events: bool) -> Self {
+        Self {
+            root_span_name_on_events,
+            ..self
+        }
+    }
+
+    /// Configures the output of `#[instrument(ret, err)]` functions to be recorded on their span,
+    /// as `return.value`, `status.code`, and `status.message` properties.
+    ///
+    /// Default is `None`, which records the output as ordinary events.
+    pub fn with_instrument_output(self, returns: ReturnCapture) -> Self {
+        Self {
+            return_capture: Some(returns),
+            ..self
+        }
+    }
+
+    /// Configures events to be buffered per thread and added to their span in batches of up to
+    /// `max_events`, or once the oldest buffered event is older than `max_delay`.
+    ///
+    /// Batched events keep their original time in an `event.time_unix_ns` property, which
+    /// [`BackdatingReporter`] restores.
+    ///
+    /// Default is `None`, which adds events to their span right away.
+    pub fn with_event_batching(self, max_events: usize, max_delay: Duration) -> Self {
+        Self {
+            event_batcher: Some(Box::new(EventBatcher::new(max_events, max_delay))),
+            ..self
+        }
+    }
+
+    /// Configures whether contextual events are recorded directly on the fastrace local parent
+    /// of the current thread, if one is set, without locking their tracing span.
+    ///
+    /// Default is `false`.
+    pub fn with_local_parent_events(self, local_parent_events: bool) -> Self {
+        Self {
+            local_parent_events,
+            ..self
+        }
+    }
+
+    /// Configures whether an `error.summary` event is added to spans that close in a failed
+    /// state, i.e., with an error field or an `ERROR` event.
+    ///
+    /// The event has the following properties:
+    /// - `exception.message`: The recorded error, or the message of the first `ERROR` event
+    /// - `error.count`: The number of `ERROR` events recorded within the span
+    /// - `duration_ns`: The duration of the span so far, if known
+    ///
+    /// Default is `false`.
+    pub fn with_error_summary(self, error_summary: bool) -> Self {
+        Self {
+            error_summary,
+            ..self
+        }
+    }
+
+    /// Configures a `span.summary` event to be added to every bridged span when it closes.
+    ///
+    /// The event has the following properties:
+    /// - `span.name`: The name of the span
+    /// - `duration_ns`: The duration of the span, if known
+    /// - `status.code`: `ERROR` if an error field or an `ERROR` event was recorded, or `OK`
+    /// - The last recorded value of each of the span properties named in `properties`
+    ///
+    /// Default is `None`, which adds no summary event.
+    pub fn with_close_summary(
+        self,
+        properties: impl IntoIterator<Item = impl Into<Cow<'static, str>>>,
+    ) -> Self {
+        Self {
+            close_summary: Some(properties.into_iter().map(Into::into).collect()),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of child spans recorded per bridged span.
+    ///
+    /// The number of dropped children is recorded on the parent as a `children.dropped`
+    /// property.
+    ///
+    /// Default is `None`, which records every child span.
+    pub fn with_max_children_per_span(self, max: usize) -> Self {
+        Self {
+            max_children_per_span: Some(max),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of bridged spans open at once, and the [`InFlightPolicy`]
+    /// applied to new spans while it is reached.
+    ///
+    /// Default is `None`, which does not limit the number of open spans.
+    pub fn with_max_spans_in_flight(self, max: usize, policy: InFlightPolicy) -> Self {
+        Self {
+            spans_in_flight: Some(Box::new(InFlightSpans::new(max, policy))),
+            ..self
+        }
+    }
+
+    /// Configures the maximum depth of bridged spans, roots having a depth of 1. Deeper spans
+    /// are recorded as events on their ancestor at depth `max`.
+    ///
+    /// Default is `None`, which records spans at any depth.
+    pub fn with_max_depth(self, max: usize) -> Self {
+        Self {
+            max_depth: Some(max),
+            ..self
+        }
+    }
+
+    /// Configures bridged spans open for longer than `threshold` to be reported once, by a
+    /// `long_lived_span` child span.
+    ///
+    /// The report has the following properties:
+    /// - `span.name`: The name of the offending span
+    /// - `span.target`: The target of the offending span
+    /// - `span.open_duration_ns`: How long the offending span had been open
+    ///
+    /// Default is `None`, which disables the detection.
+    pub fn with_long_lived_span_detection(self, threshold: Duration) -> Self {
+        let deadlines = self.deadlines.unwrap_or_default();
+        Self {
+            deadlines: Some(Box::new(deadlines.with_long_lived_threshold(threshold))),
+            ..self
+        }
+    }
+
+    /// Configures bridged spans to be force-ended with a `timed_out = true` property after
+    /// `timeout`, even if their tracing span is still open.
+    ///
+    /// Default is `None`, which never force-ends spans.
+    pub fn with_span_timeout(self, timeout: Duration) -> Self {
+        let deadlines = self.deadlines.unwrap_or_default();
+        Self {
+            deadlines: Some(Box::new(deadlines.with_timeout(timeout))),
+            ..self
+        }
+    }
+
+    /// Configures bridged spans to report their progress every `interval` while they are open,
+    /// with a `span.heartbeat` span reported right away.
+    ///
+    /// The heartbeat has the following properties:
+    /// - `span.name`: The name of the running span
+    /// - `span.elapsed_ns`: How long the running span has been open
+    /// - `span.event_count`: The number of events recorded within the running span so far
+    ///
+    /// Default is `None`, which reports no heartbeat.
+    pub fn with_heartbeats(self, interval: Duration) -> Self {
+        let deadlines = self.deadlines.unwrap_or_default();
+        Self {
+            deadlines: Some(Box::new(deadlines.with_heartbeat_interval(interval))),
+            ..self
+        }
+    }
+
+    /// Configures bridged spans to be split into sequential segments, reported as they end, once
+    /// the current segment has recorded `max_events` events or has been open for `max_duration`.
+    ///
+    /// Segments are numbered by a `segment.index` property, and link to the previous segment
+    /// with a `segment.previous_span_id` property.
+    ///
+    /// Default is `None`, which never splits spans.
+    pub fn with_span_segmentation(self, max_events: usize, max_duration: Duration) -> Self {
+        Self {
+            span_segmentation: Some((max_events, max_duration)),
+            ..self
+        }
+    }
+
+    /// Configures how values recorded on a tracing span after its bridged span timed out are
+    /// handled, see [`LateRecords`].
+    ///
+    /// Default is [`LateRecords::Event`].
+    pub fn with_late_records(self, late_records: LateRecords) -> Self {
+        Self {
+            late_records,
+            ..self
+        }
+    }
+
+    /// Configures an approximate memory budget of `limit` bytes per bridged span, and the
+    /// [`SpillPolicy`] applied once it is exceeded.
+    ///
+    /// The amount of discarded data is recorded in the `budget.dropped_events`,
+    /// `budget.dropped_properties`, and `budget.truncated_values` properties.
+    ///
+    /// Default is `None`, which does not limit the size of spans.
+    pub fn with_span_memory_budget(self, limit: usize, policy: SpillPolicy) -> Self {
+        Self {
+            span_memory_budget: Some((limit, policy)),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of properties recorded per event.
+    ///
+    /// The number of dropped properties is recorded on the event as a `properties.dropped`
+    /// property.
+    ///
+    /// Default is `None`, which does not limit the number of properties of events.
+    pub fn with_max_properties_per_event(self, max: usize) -> Self {
+        Self {
+            max_properties_per_event: Some(max),
+            ..self
+        }
+    }
+
+    /// Installs an [`AdaptiveSampler`] deciding which new traces are bridged.
+    ///
+    /// Default is `None`, which bridges every trace.
+    pub fn with_adaptive_sampling(self, sampler: AdaptiveSampler) -> Self {
+        Self {
+            adaptive_sampler: Some(Box::new(sampler)),
+            ..self
+        }
+    }
+
+    /// Configures the layer to report its own overhead every `interval`, with a
+    /// `fastrace_tracing.overhead` root span.
+    ///
+    /// The report has the following properties:
+    /// - `overhead.interval_ns`: The time elapsed since the previous report
+    /// - `overhead.on_new_span.count`, `overhead.on_event.count`: The number of calls
+    /// - `overhead.on_new_span.time_ns`, `overhead.on_event.time_ns`: The total time spent
+    /// - `overhead.spans`, `overhead.events`: The number of spans and events bridged
+    ///
+    /// Default is `None`, which does not measure the overhead.
+    pub fn with_self_telemetry(self, interval: Duration) -> Self {
+        Self {
+            self_telemetry: Some(Box::new(SelfTelemetry::new(interval))),
+            ..self
+        }
+    }
+
+    /// Configures whether the layer makes every bridging decision without creating fastrace
+    /// spans or events, see [`set_shadow_mode`](Self::set_shadow_mode).
+    ///
+    /// Default is `false`.
+    pub fn with_shadow_mode(self, shadow_mode: bool) -> Self {
+        Self {
+            shadow_mode: AtomicBool::new(shadow_mode),
+            ..self
+        }
+    }
+
+    /// Registers a [`PropertyProvider`] contributing properties to every bridged span.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new().with_property_provider(
+    ///     |_: &tracing::Metadata<'_>, properties: &mut Vec<_>| {
+    ///         properties.push(("service.name".into(), "checkout".into()));
+    ///     },
+    /// );
+    /// ```
+    pub fn with_property_provider(self, provider: impl PropertyProvider) -> Self {
+        Self {
+            span_properties: self.span_properties.with_provider(Box::new(provider)),
+            ..self
+        }
+    }
+
+    /// Adds `properties` to the root spans created by the layer only.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+    ///     .with_root_properties([("build.sha", env!("CARGO_PKG_VERSION"))]);
+    /// ```
+    pub fn with_root_properties(
+        self,
+        properties: impl IntoIterator<
+            Item = (impl Into<Cow<'static, str>>, impl Into<Cow<'static, str>>),
+        >,
+    ) -> Self {
+        let properties = properties
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()));
+        let root_properties = self.root_properties.unwrap_or_default();
+        Self {
+            root_properties: Some(Box::new(root_properties.with_properties(properties))),
+            ..self
+        }
+    }
+
+    /// Registers a [`PropertyProvider`] contributing properties to the root spans created by the
+    /// layer only.
+    pub fn with_root_property_provider(self, provider: impl PropertyProvider) -> Self {
+        let root_properties = self.root_properties.unwrap_or_default();
+        Self {
+            root_properties: Some(Box::new(root_properties.with_provider(Box::new(provider)))),
+            ..self
+        }
+    }
+
+    /// Records the value of the tokio task-local `key` as a property named `name` on every span
+    /// created within a task where it is set. Requires the `tokio` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    ///
+    /// tokio::task_local! {
+    ///     static REQUEST_ID: u64;
+    /// }
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+    ///     .with_task_local("request.id", &REQUEST_ID);
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn with_task_local<T>(
+        self,
+        name: impl Into<Cow<'static, str>>,
+        key: &'static tokio::task::LocalKey<T>,
+    ) -> Self
+    where
+        T: fmt::Display + 'static,
+    {
+        self.with_property_provider(task_local::TaskLocalProvider::new(name.into(), key))
+    }
+
+    /// Configures whether tokio task information is included in the spans created within a
+    /// spawned task. Requires the `tokio` feature.
+    ///
+    /// When enabled, span properties will include:
+    /// - `task.id`: The id of the tokio task, as shown by tokio-console
+    /// - `task.runtime`: The name of the worker thread running the task
+    ///
+    /// Default is `false`.
+    #[cfg(feature = "tokio")]
+    pub fn with_task_info(self, task_info: bool) -> Self {
+        Self {
+            span_properties: self.span_properties.with_task_info(task_info),
+            ..self
+        }
+    }
+
+    /// Configures up to `capacity` spans created before the application has set any fastrace
+    /// local parent to be held back, and emitted as children of the first local parent that
+    /// appears.
+    ///
+    /// Buffered spans carry their original timing in the `buffered.start_unix_ns` and
+    /// `buffered.duration_ns` properties.
+    ///
+    /// Default is disabled.
+    pub fn with_orphan_buffer(self, capacity: usize) -> Self {
+        Self {
+            orphan_buffer: Some(Box::new(OrphanBuffer::new(capacity))),
+            ..self
+        }
+    }
+
+    /// Configures up to `capacity` spans created before the fastrace reporter is set with
+    /// [`set_reporter`] to be held back, and replayed as new traces once it is.
+    ///
+    /// Buffered spans carry their original timing in the `buffered.start_unix_ns` and
+    /// `buffered.duration_ns` properties.
+    ///
+    /// Default is disabled.
+    pub fn with_early_buffer(self, capacity: usize) -> Self {
+        Self {
+            early_buffer: Some(EarlyBuffer::new(capacity)),
+            ..self
+        }
+    }
+
+    /// Configures whether tracing spans created with an explicit `parent: None` are attached to
+    /// the fastrace local parent, if any, rather than starting a new trace.
+    ///
+    /// Default is `false`.
+    pub fn with_explicit_roots_attached_to_local_parent(self, attach: bool) -> Self {
+        let roots = self.roots.unwrap_or_default();
+        Self {
+            roots: Some(Box::new(roots.with_explicit_roots_attached(attach))),
+            ..self
+        }
+    }
+
+    /// Configures how the roots created for spans without any parent are named, see
+    /// [`RootNaming`].
+    ///
+    /// Default is [`RootNaming::Verbatim`].
+    pub fn with_implicit_root_naming(self, naming: RootNaming) -> Self {
+        let roots = self.roots.unwrap_or_default();
+        Self {
+            roots: Some(Box::new(roots.with_naming(naming))),
+            ..self
+        }
+    }
+
+    /// Configures spans without any parent to be attached to a root per thread, named after the
+    /// thread, which is replaced once it is older than `max_age`.
+    ///
+    /// Default is `None`, which starts a new trace for each such span.
+    pub fn with_thread_roots(self, max_age: Duration) -> Self {
+        let roots = self.roots.unwrap_or_default();
+        Self {
+            roots: Some(Box::new(roots.with_thread_roots(max_age))),
+            ..self
+        }
+    }
+
+    /// Configures a [`TenantResolver`] whose result is recorded as the `tenant.id` property of
+    /// every bridged span, and inherited by its descendants.
+    ///
+    /// Default is `None`, which does not record any tenant.
+    pub fn with_tenant_resolver(self, resolver: TenantResolver) -> Self {
+        Self {
+            tenant_resolver: Some(resolver),
+            ..self
+        }
+    }
+
+    /// Configures whether events that cannot be attached to any fastrace span are forwarded to
+    /// the [`log`](https://docs.rs/log) crate. Requires the `log` feature.
+    ///
+    /// Default is `false`.
+    #[cfg(feature = "log")]
+    pub fn with_log_fallback(self, log_fallback: bool) -> Self {
+        Self {
+            log_fallback,
+            ..self
+        }
+    }
+
+    /// Configures rules selecting spans that start a new trace, linked to the trace they were
+    /// created in, see [`RootSplit`].
+    ///
+    /// Default is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    /// use fastrace_tracing::RootSplit;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new().with_root_splits([
+    ///     RootSplit::new()
+    ///         .with_name("http.request")
+    ///         .with_field("span.kind", "server"),
+    ///     RootSplit::new().with_target("my_consumer::message"),
+    /// ]);
+    /// ```
+    pub fn with_root_splits(self, rules: impl IntoIterator<Item = RootSplit>) -> Self {
+        let roots = self.roots.unwrap_or_default();
+        Self {
+            roots: Some(Box::new(roots.with_splits(rules.into_iter().collect()))),
+            ..self
+        }
+    }
+
+    /// Configures the trace id of the roots created by the layer to be derived from their
+    /// target, name, and the values of `fields`, rather than being random.
+    ///
+    /// Default is `None`, which uses random trace ids.
+    pub fn with_root_trace_id_fields(
+        self,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        let fields = fields.into_iter().map(Into::into).collect();
+        let roots = self.roots.unwrap_or_default();
+        Self {
+            roots: Some(Box::new(roots.with_trace_id_fields(fields))),
+            ..self
+        }
+    }
+
+    /// Installs a [`ShutdownHandle`], which stops the layer from bridging new spans and events
+    /// once triggered. Requires the `shutdown` feature.
+    ///
+    /// Default is `None`.
+    #[cfg(feature = "shutdown")]
+    pub fn with_shutdown(self, shutdown: ShutdownHandle) -> Self {
+        Self {
+            shutdown: Some(shutdown),
+            ..self
+        }
+    }
+
+    /// Returns `true` once the [`ShutdownHandle`] of the layer, if any, has been triggered.
+    fn is_shut_down(&self) -> bool {
+        #[cfg(feature = "shutdown")]
+        if let Some(shutdown) = &self.shutdown {
+            return shutdown.is_shut_down();
+        }
+        false
+    }
+
+    /// Returns the error message recorded among `properties`, if the error summary is enabled.
+    fn recorded_error(&self, properties: &[Property]) -> Option<String> {
+        if !self.error_summary {
+            return None;
+        }
+        properties
+            .iter()
+            .rfind(|(key, _)| key == FIELD_EXCEPTION_MESSAGE)
+            .map(|(_, value)| value.to_string())
+    }
+
+    /// Returns how a span is bridged, as decided when its callsite was registered.
+    fn span_action(&self, meta: &'static Metadata<'static>) -> SpanAction {
+        self.span_actions
+            .get(meta, || self.decide_span_action(meta))
+    }
+
+    /// Decides how the spans of a callsite are bridged, according to the filters.
+    fn decide_span_action(&self, meta: &Metadata<'_>) -> SpanAction {
+        self.filters
+            .as_ref()
+            .map_or(SpanAction::Record, |filters| filters.span_action(meta))
+    }
+
+    /// Returns a snapshot of the activity of the layer: the number of spans and events bridged
+    /// so far, and the current ratio of the [`AdaptiveSampler`], if any.
+    ///
+    /// In [shadow mode](Self::with_shadow_mode), the spans and events that would have been
+    /// bridged are counted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new();
+    /// assert_eq!(layer.stats().spans, 0);
+    /// ```
+    pub fn stats(&self) -> Stats {
+        Stats {
+            spans: self.counters.spans(),
+            events: self.counters.events(),
+            sampling_ratio: self.adaptive_sampler.as_deref().map(AdaptiveSampler::ratio),
+        }
+    }
+
+    /// Switches [shadow mode](Self::with_shadow_mode) on or off at runtime, e.g., to start
+    /// forwarding once the volume reported in shadow mode has been validated.
+    ///
+    /// The switch applies to spans and events created afterwards. Spans created in shadow mode
+    /// stay noop fastrace spans, and so do their children.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::FastraceCompatLayer;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new().with_shadow_mode(true);
+    /// layer.set_shadow_mode(false);
+    /// ```
+    pub fn set_shadow_mode(&self, shadow_mode: bool) {
+        self.shadow_mode.store(shadow_mode, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the layer is in [shadow mode](Self::with_shadow_mode).
+    fn is_shadow_mode(&self) -> bool {
+        self.shadow_mode.load(Ordering::Relaxed)
+    }
+
+    /// Returns how the spans or events of the callsite with the given metadata are treated,
+    /// according to the configuration.
+    ///
+    /// This evaluates the span policy, the noise filter, the level thresholds, and the other
+    /// filters without recording anything, so that tests and configuration linters can verify
+    /// them. Decisions depending on the fields or the context of spans, e.g., the
+    /// `fastrace.skip` field or the maximum depth, are not taken into account.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastrace_tracing::Decision;
+    /// use fastrace_tracing::FastraceCompatLayer;
+    /// use tracing::Level;
+    ///
+    /// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+    ///     .with_span_level_threshold(Level::INFO);
+    ///
+    /// let span = tracing::debug_span!("parse");
+    /// if let Some(meta) = span.metadata() {
+    ///     assert_eq!(layer.evaluate(meta), Decision::Demote);
+    /// }
+    /// ```
+    pub fn evaluate(&self, meta: &'static Metadata<'static>) -> Decision {
+        if meta.is_span() {
+            return match self.span_action(meta) {
+                SpanAction::Record if self.adaptive_sampler.is_some() => Decision::Sample,
+                SpanAction::Record => Decision::Record,
+                SpanAction::Discard => Decision::Drop,
+                SpanAction::EventOnly => Decision::Demote,
+                SpanAction::Aggregate => Decision::Aggregate,
+            };
+        }
+
+        if !self.is_event_enabled(meta) {
+            return Decision::Drop;
+        }
+        match self.event_folding {
+            Some((threshold, _)) if *meta.level() > threshold => Decision::Demote,
+            _ => Decision::Record,
+        }
+    }
+
+    /// Returns `true` if an event with the given metadata passes the filters.
+    fn is_event_enabled(&self, meta: &Metadata<'_>) -> bool {
+        self.filters
+            .as_ref()
+            .map_or(true, |filters| filters.is_event_enabled(meta))
+    }
+
+    /// Returns the properties recorded on every event, according to the configuration.
+    ///
+    /// The values are borrowed from the static metadata, so that events without fields other than
+    /// `message` only allocate their name, their line number, and the property list itself.
+    fn event_properties(&self, meta: &'static Metadata<'static>) -> Vec<Property> {
+        let mut props: Vec<Property> = Vec::with_capacity(5);
+        props.push(("level".into(), meta.level().as_str().into()));
+        props.push(("target".into(), meta.target().into()));
+
+        #[cfg(not(feature = "strip-location"))]
+        if self.span_properties.records_location() {
+            if let Some(file) = meta.file() {
+                let file = self.span_properties.code_filepath(file);
+                props.push(("code.filepath".into(), file.into()));
+            }
+            if let Some(module) = meta.module_path() {
+                props.push(("code.namespace".into(), module.into()));
+            }
+            if let Some(line) = meta.line() {
+                props.push(("code.lineno".into(), line.to_string().into()));
+            }
+        }
+
+        props
+    }
+
+    fn get_context(dispatch: &Dispatch, id: &span::Id, f: &mut dyn FnMut(&mut ExtensionsMut<'_>)) {
+        let subscriber = dispatch
+            .downcast_ref::<S>()
+            .expect("subscriber should downcast to expected type, this is a bug");
+        let Some(span) = subscriber.span(id) else {
+            return;
+        };
+        let _holding = reentrancy::hold_extensions();
+        let mut extensions = span.extensions_mut();
+        f(&mut extensions);
+    }
+
+    /// Converts a tracing event recorded within `span` into a fastrace event named `name`.
+    fn convert_event(
+        &self,
+        name: Cow<'static, str>,
+        event: &Event<'_>,
+        span: Option<&SpanRef<'_, S>>,
+    ) -> fastrace::Event {
+        let properties = self.convert_event_properties(event, span);
+        fastrace::Event::new(name).with_properties(|| properties)
+    }
+
+    /// Returns the properties of the fastrace event converted from a tracing event recorded
+    /// within `span`, if any.
+    fn convert_event_properties(
+        &self,
+        event: &Event<'_>,
+        span: Option<&SpanRef<'_, S>>,
+    ) -> Vec<Property> {
+        let mut properties = self.event_properties(event.metadata());
+        if let Some(span) = span.filter(|_| self.span_name_on_events) {
+            properties.push(("span.name".into(), span.name().into()));
+        }
+        if let Some(span) = span.filter(|_| self.root_span_name_on_events) {
+            if let Some(root) = span.scope().last() {
+                properties.push(("span.root_name".into(), root.name().into()));
+            }
+        }
+        event.record(&mut EventVisitor {
+            properties: &mut properties,
+            options: self.field_options,
+        });
+        #[cfg(feature = "fmt")]
+        if let Some(fields) = self
+            .fields_formatter
+            .as_ref()
+            .and_then(|formatter| formatter.format_event(event))
+        {
+            properties.push((FIELD_FORMATTED_FIELDS.into(), fields.into()));
+        }
+        self.transform_properties(&mut properties);
+        if let Some(max) = self.max_properties_per_event {
+            let dropped = properties.len().saturating_sub(max);
+            if dropped > 0 {
+                properties.truncate(max);
+                properties.push(("properties.dropped".into(), dropped.to_string().into()));
+            }
+        }
+        properties
+    }
+
+    /// Applies the configured transformations to a property value or event name.
+    fn transform_value(&self, value: &mut Cow<'static, str>) {
+        if let Some(sanitizer) = &self.sanitizer {
+            sanitizer.sanitize(value);
+        }
+    }
+
+    /// Forwards an event that cannot be attached to any fastrace span to the `log` crate, if
+    /// [`with_log_fallback`](Self::with_log_fallback) is enabled.
+    #[cfg(feature = "log")]
+    fn log_dropped_event(&self, event: &Event<'_>) {
+        if !self.log_fallback {
+            return;
+        }
+        let mut properties = Vec::new();
+        event.record(&mut EventVisitor {
+            properties: &mut properties,
+            options: self.field_options,
+        });
+        self.transform_properties(&mut properties);
+        fallback::log_event(event.metadata(), &self.event_name(event), &properties);
+    }
+
+    /// Starts or ends the span synthesized from `event` by `bridge`, if `event` is one of its
+    /// pairs of events.
+    ///
+    /// Returns `false` if `event` should be recorded as an ordinary event.
+    fn bridge_event_pair(
+        &self,
+        bridge: &EventToSpanBridge,
+        event: &Event<'_>,
+        span: Option<&SpanRef<'_, S>>,
+    ) -> bool {
+        let Some((index, edge, key)) = bridge.match_event(event) else {
+            return false;
+        };
+        let mut properties = Vec::new();
+        event.record(&mut EventVisitor {
+            properties: &mut properties,
+            options: self.field_options,
+        });
+        self.transform_properties(&mut properties);
+
+        match edge {
+            Edge::Start => {
+                let name = bridge.name(index);
+                let fastrace_span = match span.and_then(find_recorded_ancestor) {
+                    Some(parent) => parent
+                        .extensions()
+                        .get::<fastrace::Span>()
+                        .map(|parent| fastrace::Span::enter_with_parent(name, parent))
+                        .unwrap_or_default(),
+                    None if self.is_shadow_mode() => fastrace::Span::noop(),
+                    None => fastrace::Span::enter_with_local_parent(name),
+                };
+                bridge.start(index, key, fastrace_span.with_properties(|| properties))
+            }
+            Edge::End => match bridge.end(index, key) {
+                Some(fastrace_span) => {
+                    fastrace_span.add_properties(|| properties);
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Returns the name of the fastrace event bridged from `event`.
+    fn event_name(&self, event: &Event<'_>) -> Cow<'static, str> {
+        let mut name = event_name(event);
+        if self.target_in_event_names {
+            name = format!("{}: {}", event.metadata().target(), name).into();
+        }
+        self.transform_value(&mut name);
+        name
+    }
+
+    /// Returns the [`EventBatcher`], unless batching is bypassed because events need to be
+    /// inspected under the lock of their span.
+    fn event_batcher(&self) -> Option<&EventBatcher> {
+        self.event_batcher
+            .as_deref()
+            .filter(|_| !self.inspects_events() && !self.events_as_spans)
+            .filter(|_| !capture::is_capturing())
+    }
+
+    /// Returns `true` if events need to be inspected under the lock of their span.
+    fn inspects_events(&self) -> bool {
+        self.event_folding.is_some()
+            || self.error_summary
+            || self.close_summary.is_some()
+            || self
+                .deadlines
+                .as_deref()
+                .is_some_and(Deadlines::has_heartbeats)
+            || self.span_segmentation.is_some()
+            || self.span_memory_budget.is_some()
+    }
+
+    /// Adds a contextual event to the fastrace local parent, if
+    /// [`with_local_parent_events`](Self::with_local_parent_events) is enabled and a local
+    /// parent is set.
+    ///
+    /// Returns `false` if the event was not recorded.
+    fn add_local_parent_event(&self, event: &Event<'_>, span: Option<&SpanRef<'_, S>>) -> bool {
+        if !self.local_parent_events
+            || self.is_shadow_mode()
+            || capture::is_capturing()
+            || !event.is_contextual()
+            || SpanContext::current_local_parent().is_none()
+        {
+            return false;
+        }
+        let fastrace_event = self.convert_event(self.event_name(event), event, span);
+        fastrace::local::LocalSpan::add_event(fastrace_event);
+        self.count_event();
+        true
+    }
+
+    /// Applies the configured transformations to properties recorded from fields, before they
+    /// are forwarded to fastrace.
+    fn transform_properties(&self, properties: &mut Vec<Property>) {
+        if let Some(sanitizer) = &self.sanitizer {
+            sanitizer.apply(properties);
+        }
+    }
+
+    /// Returns the parent of a new span with the given attributes, as seen by this layer.
+    fn parent_span<'a>(
+        &self,
+        attrs: &Attributes<'_>,
+        ctx: &'a Context<'_, S>,
+    ) -> Option<SpanRef<'a, S>> {
+        if let Some(parent) = attrs.parent() {
+            ctx.span(parent)
+        } else if attrs.is_contextual() {
+            ctx.lookup_current()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the context of the fastrace span a new span with the given attributes would be
+    /// attached to, if any.
+    fn parent_context(&self, attrs: &Attributes<'_>, ctx: &Context<'_, S>) -> Option<SpanContext> {
+        match self
+            .parent_span(attrs, ctx)
+            .and_then(|span| find_recorded_ancestor(&span))
+        {
+            Some(parent) => parent
+                .extensions()
+                .get::<fastrace::Span>()
+                .and_then(SpanContext::from_span),
+            None if attrs.is_contextual() => SpanContext::current_local_parent(),
+            None => None,
+        }
+    }
+
+    /// Returns `true` if the bridged span with the given context belongs to a trace started by
+    /// the layer, whose sampling was decided by the [`AdaptiveSampler`].
+    ///
+    /// Spans attached to a fastrace parent set by the application are not sampled.
+    fn is_sampled_trace(
+        &self,
+        attrs: &Attributes<'_>,
+        ctx: &Context<'_, S>,
+        context: SpanContext,
+    ) -> bool {
+        match self
+            .parent_span(attrs, ctx)
+            .and_then(|span| find_recorded_ancestor(&span))
+        {
+            Some(parent) => {
+                let extensions = parent.extensions();
+                let parent_trace = extensions
+                    .get::<fastrace::Span>()
+                    .and_then(SpanContext::from_span)
+                    .map(|parent| parent.trace_id);
+                parent_trace != Some(context.trace_id) || extensions.get::<SampledTrace>().is_some()
+            }
+            None => attrs
+                .is_contextual()
+                .then(SpanContext::current_local_parent)
+                .flatten()
+                .map_or(true, |parent| parent.trace_id != context.trace_id),
+        }
+    }
+
+    /// Counts a bridged span in the stats and self telemetry.
+    fn count_span(&self) {
+        self.counters.count_span();
+        if let Some(telemetry) = &self.self_telemetry {
+            telemetry.count_span();
+        }
+    }
+
+    /// Counts a bridged event in the stats and self telemetry.
+    fn count_event(&self) {
+        self.counters.count_event();
+        if let Some(telemetry) = &self.self_telemetry {
+            telemetry.count_event();
+        }
+    }
+
+    /// Records a span that is not materialized as a single event on its nearest recorded
+    /// ancestor.
+    fn record_span_as_event(
+        &self,
+        attrs: &Attributes<'_>,
+        span: &SpanRef<'_, S>,
+        ctx: &Context<'_, S>,
+    ) {
+        span.extensions_mut().insert(EventOnlySpan);
+
+        let meta = attrs.metadata();
+        let mut properties = self.event_properties(meta);
+        if !self.ignore_span_fields {
+            attrs.record(&mut EventVisitor {
+                properties: &mut properties,
+                options: self.field_options,
+            });
+        }
+        self.transform_properties(&mut properties);
+        let fastrace_event = fastrace::Event::new(meta.name()).with_properties(|| properties);
+
+        match self
+            .parent_span(attrs, ctx)
+            .and_then(|span| find_recorded_ancestor(&span))
+        {
+            Some(ancestor) => {
+                if let Some(parent) = ancestor.extensions().get::<fastrace::Span>() {
+                    parent.add_event(fastrace_event);
+                }
+            }
+            None if self.is_shadow_mode() => {}
+            None => fastrace::local::LocalSpan::add_event(fastrace_event),
+        }
+    }
+
+    /// Handles the deadlines of the bridged spans that have passed: reports long-lived spans,
+    /// force-ends timed out spans, and emits heartbeats.
+    fn check_deadlines(&self, ctx: &Context<'_, S>) {
+        let Some(scheduler) = &self.deadlines else {
+            return;
+        };
+        for (id, seq) in scheduler.take_due() {
+            let Some(span) = ctx.span(&id) else {
+                continue;
+            };
+            let mut extensions = span.extensions_mut();
+            let Some(mut deadlines) = extensions
+                .remove::<SpanDeadlines>()
+                .filter(|deadlines| deadlines.seq() == seq)
+            else {
+                continue;
+            };
+            for deadline in scheduler.take_passed(&mut deadlines) {
+                match deadline {
+                    Deadline::LongLived => report_long_lived_span(
+                        span.metadata(),
+                        deadlines.context(),
+                        deadlines.elapsed(),
+                    ),
+                    Deadline::Timeout => {
+                        if let Some(fastrace_span) = extensions.remove::<fastrace::Span>() {
+                            extensions.insert(EndedSpan);
+                            if let Some(budget) = extensions.remove::<SpanBudget>() {
+                                budget.finish(&fastrace_span);
+                            }
+                            fastrace_span.add_property(|| ("timed_out", "true"));
+                        }
+                    }
+                    Deadline::Heartbeat => emit_heartbeat(
+                        span.name(),
+                        deadlines.context(),
+                        deadlines.elapsed(),
+                        deadlines.events(),
+                    ),
+                }
+            }
+            scheduler.schedule(id, &mut deadlines);
+            extensions.insert(deadlines);
+        }
+    }
+
+    /// Starts a new segment of a bridged span once its current segment is complete, see
+    /// [`with_span_segmentation`](Self::with_span_segmentation).
+    fn segment_span(&self, extensions: &mut ExtensionsMut<'_>) {
+        let Some((max_events, max_duration)) = self.span_segmentation else {
+            return;
+        };
+        let Some(mut segments) = extensions.remove::<SpanSegments>() else {
+            return;
+        };
+        if segments.record_event(max_events, max_duration) {
+            if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+                let next = segments.next_segment(fastrace_span);
+                // Ends the current segment.
+                *fastrace_span = next;
+            }
+        }
+        extensions.insert(segments);
+    }
+
+    /// Counts a new span with the given attributes against the child cap of its recorded parent.
+    ///
+    /// Returns `true` if the cap is exceeded, in which case the span should be dropped.
+    fn exceeds_max_children(&self, attrs: &Attributes<'_>, ctx: &Context<'_, S>) -> bool {
+        let Some(max) = self.max_children_per_span else {
+            return false;
+        };
+        let Some(parent) = self
+            .parent_span(attrs, ctx)
+            .and_then(|span| find_recorded_ancestor(&span))
+        else {
+            return false;
+        };
+
+        let mut extensions = parent.extensions_mut();
+        let children = get_or_insert_default::<ChildCount>(&mut extensions);
+        if children.recorded < max {
+            children.recorded += 1;
+            false
+        } else {
+            children.dropped += 1;
+            true
+        }
+    }
+
+    /// Returns the depth a new span with the given attributes has among bridged spans.
+    fn span_depth(&self, attrs: &Attributes<'_>, ctx: &Context<'_, S>) -> usize {
+        self.parent_span(attrs, ctx)
+            .and_then(|span| find_recorded_ancestor(&span))
+            .and_then(|parent| parent.extensions().get::<SpanDepth>().map(|depth| depth.0))
+            .map_or(1, |depth| depth + 1)
+    }
+
+    /// Returns the sequence number of the pending parent if a new span with the given
+    /// attributes should be buffered by the orphan buffer, i.e., it has no recorded ancestor,
+    /// and either is nested in a buffered span, or there is no fastrace local parent yet.
+    fn orphan_parent(
+        &self,
+        buffer: &OrphanBuffer,
+        attrs: &Attributes<'_>,
+        ctx: &Context<'_, S>,
+    ) -> Option<Option<u64>> {
+        if !attrs.is_contextual() {
+            return None;
+        }
+        let parent = ctx.lookup_current();
+        if parent.as_ref().and_then(find_recorded_ancestor).is_some() {
+            return None;
+        }
+
+        // Children of buffered spans are buffered as well, so that they are emitted under them.
+        let pending_parent = parent.and_then(|parent| {
+            parent
+                .scope()
+                .filter(|span| span.extensions().get::<EarlySpan>().is_none())
+                .find_map(|span| span.extensions().get::<PendingSpan>().map(|p| p.seq()))
+        });
+        if pending_parent.is_none()
+            && (!buffer.is_buffering() || SpanContext::current_local_parent().is_some())
+        {
+            return None;
+        }
+        Some(pending_parent)
+    }
+
+    /// Returns the span held back by the [`EarlyBuffer`] that a new span buffered by it is
+    /// nested in, if any.
+    fn early_parent(
+        &self,
+        attrs: &Attributes<'_>,
+        ctx: &Context<'_, S>,
+        root: bool,
+    ) -> Option<u64> {
+        if root {
+            return None;
+        }
+        self.parent_span(attrs, ctx)?.scope().find_map(|span| {
+            let extensions = span.extensions();
+            extensions.get::<EarlySpan>()?;
+            extensions.get::<PendingSpan>().map(|p| p.seq())
+        })
+    }
+
+    /// Records the placeholder configured with
+    /// [`with_empty_field_placeholder`](Self::with_empty_field_placeholder) for each field of the
+    /// span that was declared without a value.
+    fn record_empty_fields(&self, attrs: &Attributes<'_>, properties: &mut Vec<Property>) {
+        let Some(placeholder) = &self.empty_field_placeholder else {
+            return;
+        };
+        let mut recorded = RecordedFields(Vec::new());
+        attrs.record(&mut recorded);
+        for field in attrs.metadata().fields() {
+            let name = field.name();
+            if recorded.0.contains(&field.index())
+                || name.starts_with(FIELD_BAGGAGE_PREFIX)
+                || matches!(
+                    name,
+                    FIELD_LINKS
+                        | FIELD_FASTRACE_ROOT
+                        | FIELD_FASTRACE_NAME
+                        | FIELD_FASTRACE_SKIP
+                        | FIELD_FASTRACE_EVENT_ONLY
+                        | FIELD_SAMPLE_RATE
+                )
+            {
+                continue;
+            }
+            let key = self.field_options.key(name);
+            properties.push((key.into(), placeholder.clone()));
+        }
+    }
+
+    /// Converts the span fields whose conversion was deferred by
+    /// [`with_lazy_fields`](Self::with_lazy_fields) to properties.
+    fn lazy_properties(&self, lazy: LazyFields) -> Vec<Property> {
+        let mut properties = Vec::new();
+        lazy.replay(&mut SpanAttributeVisitor {
+            properties: &mut properties,
+            baggage: &mut Baggage::new(),
+            control: &mut ControlFields::default(),
+            lazy: None,
+            drop_if: None,
+            options: self.field_options,
+        });
+        self.transform_properties(&mut properties);
+        properties
+    }
+
+    /// Records the span fields whose conversion was deferred on the span, if any.
+    ///
+    /// fastrace only evaluates properties of spans that are still going to be reported, so the
+    /// fields of cancelled spans are never converted.
+    fn record_lazy_fields(&self, extensions: &mut ExtensionsMut<'_>) {
+        let Some(lazy) = extensions.remove::<LazyFields>() else {
+            return;
+        };
+        let mut budget = extensions.remove::<SpanBudget>();
+        if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+            fastrace_span.add_properties(|| {
+                let mut properties = self.lazy_properties(lazy);
+                if let Some(budget) = &mut budget {
+                    budget.admit_properties(&mut properties);
+                }
+                properties
+            });
+        }
+        if let Some(budget) = budget {
+            extensions.insert(budget);
+        }
+    }
+
+    /// Returns the parent context of a root span created by the layer.
+    fn root_context(&self, meta: &Metadata<'_>, properties: &[Property]) -> SpanContext {
+        self.roots
+            .as_ref()
+            .map_or_else(SpanContext::random, |roots| roots.context(meta, properties))
+    }
+
+    /// Creates a root span with the properties configured for root spans.
+    fn new_root_span(
+        &self,
+        name: Cow<'static, str>,
+        parent: SpanContext,
+        meta: &Metadata<'_>,
+    ) -> fastrace::Span {
+        if let Some(sampler) = &self.adaptive_sampler {
+            if !sampler.sample(parent.trace_id) {
+                return fastrace::Span::noop();
+            }
+        }
+        let root = fastrace::Span::root(name, parent);
+        match &self.root_properties {
+            Some(root_properties) => root_properties.apply(root, meta),
+            None => root,
+        }
+    }
+
+    fn new_fastrace_span(
+        &self,
+        attrs: &Attributes<'_>,
+        ctx: &Context<'_, S>,
+        name: Cow<'static, str>,
+        properties: &[Property],
+    ) -> fastrace::Span {
+        if let Some(parent) = attrs.parent() {
+            // A span can have an _explicit_ parent that is NOT seen by this `Layer` (for which
             // `Context::span` returns `None`. This happens if the parent span is filtered away
             // from the layer by a per-layer filter. In that case, we fall-through to the `else`
             // case, and consider this span a root span.
             if let Some(span) = ctx.span(parent) {
-                let extensions = span.extensions();
-                return extensions
-                    .get::<fastrace::Span>()
-                    .map(|parent| {
-                        fastrace::Span::enter_with_parent(attrs.metadata().name(), parent)
+                return find_recorded_ancestor(&span)
+                    .and_then(|span| {
+                        let extensions = span.extensions();
+                        extensions
+                            .get::<fastrace::Span>()
+                            .map(|parent| fastrace::Span::enter_with_parent(name.clone(), parent))
                     })
                     .unwrap_or_default();
             }
@@ -337,22 +3006,42 @@ where
         // Else if the span is inferred from context, look up any available current span.
         if attrs.is_contextual() {
             ctx.lookup_current()
+                .and_then(|span| find_recorded_ancestor(&span))
                 .and_then(|span| {
                     let extensions = span.extensions();
-                    extensions.get::<fastrace::Span>().map(|parent| {
-                        fastrace::Span::enter_with_parent(attrs.metadata().name(), parent)
-                    })
+                    extensions
+                        .get::<fastrace::Span>()
+                        .map(|parent| fastrace::Span::enter_with_parent(name.clone(), parent))
                 })
                 .or_else(|| {
                     SpanContext::current_local_parent()
-                        .map(|_| fastrace::Span::enter_with_local_parent(attrs.metadata().name()))
+                        .map(|_| fastrace::Span::enter_with_local_parent(name.clone()))
+                })
+                .or_else(|| {
+                    let max_age = self.roots.as_ref()?.thread_roots()?;
+                    thread_root::enter_with_thread_root(name.clone(), max_age, |root_name| {
+                        self.new_root_span(root_name, SpanContext::random(), attrs.metadata())
+                    })
                 })
                 .unwrap_or_else(|| {
-                    fastrace::Span::root(attrs.metadata().name(), SpanContext::random())
+                    let parent = self.root_context(attrs.metadata(), properties);
+                    let name = match &self.roots {
+                        Some(roots) => roots.implicit_root_name(name, attrs.metadata()),
+                        None => name,
+                    };
+                    self.new_root_span(name, parent, attrs.metadata())
                 })
-        // Explicit root spans should have no parent context.
+        // Explicit root spans should have no parent context, unless configured otherwise.
+        } else if self
+            .roots
+            .as_ref()
+            .is_some_and(|roots| roots.attaches_explicit_roots())
+            && SpanContext::current_local_parent().is_some()
+        {
+            fastrace::Span::enter_with_local_parent(name)
         } else {
-            fastrace::Span::root(attrs.metadata().name(), SpanContext::random())
+            let parent = self.root_context(attrs.metadata(), properties);
+            self.new_root_span(name, parent, attrs.metadata())
         }
     }
 }
@@ -366,6 +3055,133 @@ where
     }
 }
 
+/// Returns the closest span in the scope of `span`, including itself, that has been
+/// materialized as a fastrace span.
+fn find_recorded_ancestor<'a, S>(span: &SpanRef<'a, S>) -> Option<SpanRef<'a, S>>
+where
+    S: for<'span> LookupSpan<'span>,
+{
+    span.scope()
+        .find(|span| span.extensions().get::<fastrace::Span>().is_some())
+}
+
+/// Adds `events` to the fastrace span bridged from `span`, if it is still recorded.
+///
+/// This only takes a read lock on the extensions of the span, as fastrace spans accept events
+/// through a shared reference.
+fn add_events<S>(span: &SpanRef<'_, S>, events: Vec<fastrace::Event>)
+where
+    S: for<'span> LookupSpan<'span>,
+{
+    if let Some(fastrace_span) = span.extensions().get::<fastrace::Span>() {
+        for event in events {
+            fastrace_span.add_event(event);
+        }
+    }
+}
+
+/// Stores the state inherited by the descendants of a new span in its extensions, whichever
+/// way the span is bridged.
+fn insert_inherited<S>(
+    span: &SpanRef<'_, S>,
+    baggage: Baggage,
+    tenant: Option<Tenant>,
+    depth: Option<SpanDepth>,
+) where
+    S: for<'span> LookupSpan<'span>,
+{
+    if baggage.is_empty() && tenant.is_none() && depth.is_none() {
+        return;
+    }
+    let mut extensions = span.extensions_mut();
+    if !baggage.is_empty() {
+        extensions.insert(baggage);
+    }
+    if let Some(tenant) = tenant {
+        extensions.insert(tenant);
+    }
+    if let Some(depth) = depth {
+        extensions.insert(depth);
+    }
+}
+
+/// Returns the `T` extension of a span, inserting its default value if missing.
+fn get_or_insert_default<'a, T>(extensions: &'a mut ExtensionsMut<'_>) -> &'a mut T
+where
+    T: Default + Send + Sync + 'static,
+{
+    if extensions.get_mut::<T>().is_none() {
+        extensions.insert(T::default());
+    }
+    extensions
+        .get_mut::<T>()
+        .expect("extension should have been inserted")
+}
+
+/// Returns the message of `event`, or its metadata name if it has no message.
+fn event_name(event: &Event<'_>) -> Cow<'static, str> {
+    let mut name_finder = EventNameFinder { name: None };
+    event.record(&mut name_finder);
+    name_finder
+        .name
+        .unwrap_or_else(|| Cow::Borrowed(event.metadata().name()))
+}
+
+/// Records a link to `link` as `link.N.trace_id` and `link.N.span_id` properties, after the links
+/// already recorded among `properties`.
+fn push_link(properties: &mut Vec<Property>, link: SpanContext) {
+    let index = properties
+        .iter()
+        .filter(|(key, _)| key.starts_with("link.") && key.ends_with(".trace_id"))
+        .count();
+    properties.push((
+        format!("link.{}.trace_id", index).into(),
+        format!("{:032x}", link.trace_id.0).into(),
+    ));
+    properties.push((
+        format!("link.{}.span_id", index).into(),
+        format!("{:016x}", link.span_id.0).into(),
+    ));
+}
+
+/// Returns `true` if `event` is marked to be recorded as a child span.
+fn is_marked_as_span(event: &Event<'_>) -> bool {
+    if event
+        .metadata()
+        .fields()
+        .field(FIELD_FASTRACE_AS_SPAN)
+        .is_none()
+    {
+        return false;
+    }
+    let mut finder = AsSpanFinder { as_span: false };
+    event.record(&mut finder);
+    finder.as_span
+}
+
+/// Returns `true` if a span with the given sample rate is bridged in the trace of
+/// `fastrace_span`.
+///
+/// The decision only depends on the trace id, so that it is the same for all spans of a trace.
+fn is_sampled(fastrace_span: &fastrace::Span, rate: f64) -> bool {
+    if rate >= 1.0 {
+        return true;
+    }
+    let Some(context) = SpanContext::from_span(fastrace_span) else {
+        return true;
+    };
+    trace_ratio(context.trace_id) < rate
+}
+
+/// Maps `trace_id` uniformly to a ratio between 0 and 1, for sampling decisions.
+fn trace_ratio(trace_id: TraceId) -> f64 {
+    (trace_id.0 >> 64) as u64 as f64 / u64::MAX as f64
+}
+
+fn level_name(level: Level) -> String {
+    level.as_str().to_ascii_lowercase()
+}
+
 thread_local! {
     static THREAD_ID: LazyCell<u64> = LazyCell::new(|| {
         thread_id_integer(thread::current().id())
@@ -385,106 +3201,725 @@ impl<S> Layer<S> for FastraceCompatLayer<S>
 where
     S: Subscriber + for<'span> LookupSpan<'span>,
 {
+    fn register_callsite(&self, meta: &'static Metadata<'static>) -> Interest {
+        if meta.is_span() {
+            self.span_actions
+                .insert(meta, self.decide_span_action(meta));
+        }
+        Interest::always()
+    }
+
     fn on_new_span(&self, attrs: &Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if self.is_shut_down() || reentrancy::is_suppressed() {
+            return;
+        }
+        // Spans and events emitted while bridging this span, e.g., by property providers or a
+        // reporter flushing synchronously, must not be bridged in turn.
+        let _guard = reentrancy::suppress();
+        let _holding = reentrancy::hold_extensions();
+
+        let _measurement = self
+            .self_telemetry
+            .as_ref()
+            .map(|telemetry| telemetry.measure(Callback::NewSpan));
+
+        self.check_deadlines(&ctx);
+
         let span = ctx.span(id).expect("Span not found, this is a bug");
 
-        let mut fastrace_span = self.new_fastrace_span(attrs, &ctx);
+        match self.span_action(attrs.metadata()) {
+            SpanAction::Record => {}
+            SpanAction::Discard => return,
+            SpanAction::EventOnly => {
+                self.record_span_as_event(attrs, &span, &ctx);
+                return;
+            }
+            SpanAction::Aggregate => {
+                // Spans without a recorded ancestor to aggregate into are recorded as usual.
+                if self
+                    .parent_span(attrs, &ctx)
+                    .and_then(|span| find_recorded_ancestor(&span))
+                    .is_some()
+                {
+                    span.extensions_mut().insert(AggregatedSpan::new());
+                    return;
+                }
+            }
+        }
 
-        let mut props = Vec::with_capacity(8);
-        if self.location {
-            let meta = attrs.metadata();
+        if let Some(buffer) = &self.orphan_buffer {
+            buffer.flush_to_local_parent();
+        }
+        if let Some(buffer) = &self.early_buffer {
+            buffer.replay();
+        }
 
-            if let Some(filename) = meta.file() {
-                props.push(("code.filepath", filename.to_string()));
+        // Inherit the baggage of the closest ancestor carrying any.
+        let mut baggage = self
+            .parent_span(attrs, &ctx)
+            .and_then(|parent| {
+                parent
+                    .scope()
+                    .find_map(|span| span.extensions().get::<Baggage>().cloned())
+            })
+            .unwrap_or_default();
+
+        let mut properties = self.span_properties.properties(attrs.metadata());
+        if self.with_tracing_ids {
+            properties.push(("tracing.span_id".into(), id.into_u64().to_string().into()));
+            if let Some(parent) = self.parent_span(attrs, &ctx) {
+                let parent_id = parent.id().into_u64().to_string();
+                properties.push(("tracing.parent_id".into(), parent_id.into()));
+            }
+        }
+        let mut control = ControlFields::default();
+        // Fields deriving the trace id of root spans, or deciding whether spans split their
+        // trace, are needed right away.
+        let mut lazy = (self.lazy_fields
+            && !self
+                .roots
+                .as_ref()
+                .is_some_and(|roots| roots.needs_fields())
+            && !self.tenant_resolver.iter().any(TenantResolver::has_field)
+            && self.close_summary.is_none())
+        .then(LazyFields::default);
+        #[cfg(feature = "tracing-opentelemetry")]
+        let fields_start = properties.len();
+        if !self.ignore_span_fields {
+            attrs.record(&mut SpanAttributeVisitor {
+                properties: &mut properties,
+                baggage: &mut baggage,
+                control: &mut control,
+                lazy: lazy.as_mut(),
+                drop_if: self.span_drop_if.as_deref(),
+                options: self.field_options,
+            });
+            self.record_empty_fields(attrs, &mut properties);
+            #[cfg(feature = "fmt")]
+            if let Some(fields) = self
+                .fields_formatter
+                .as_ref()
+                .and_then(|formatter| formatter.format_attributes(attrs))
+            {
+                properties.push((FIELD_FORMATTED_FIELDS.into(), fields.into()));
             }
+        }
+        #[cfg(feature = "tracing-opentelemetry")]
+        if self.otel_data {
+            if let Some((otel_name, attributes)) = otel::otel_data(&span.extensions()) {
+                properties.truncate(fields_start);
+                properties.extend(attributes);
+                lazy = None;
+                control.name.get_or_insert(otel_name);
+            }
+        }
+        properties.extend(baggage.properties());
+        self.transform_properties(&mut properties);
+        let tenant = self.tenant_resolver.as_ref().and_then(|resolver| {
+            let inherited = self.parent_span(attrs, &ctx).and_then(|parent| {
+                parent
+                    .scope()
+                    .find_map(|span| span.extensions().get::<Tenant>().cloned())
+            });
+            resolver.resolve(&properties, inherited)
+        });
+        if let Some(tenant) = &tenant {
+            tenant.push_property(&mut properties);
+        }
 
-            if let Some(module) = meta.module_path() {
-                props.push(("code.namespace", module.to_string()));
+        if control.skip {
+            return;
+        }
+        if control.dropped {
+            span.extensions_mut().insert(fastrace::Span::noop());
+            return;
+        }
+        if control.event_only {
+            self.record_span_as_event(attrs, &span, &ctx);
+            return;
+        }
+        if !control.root
+            && self
+                .roots
+                .as_ref()
+                .is_some_and(|roots| roots.splits(attrs.metadata(), &properties))
+        {
+            if let Some(parent) = self.parent_context(attrs, &ctx) {
+                control.root = true;
+                push_link(&mut properties, parent);
+            }
+        }
+        let mut depth = None;
+        if let Some(max) = self.max_depth {
+            let span_depth = if control.root {
+                1
+            } else {
+                self.span_depth(attrs, &ctx)
+            };
+            if span_depth > max {
+                self.record_span_as_event(attrs, &span, &ctx);
+                return;
             }
+            depth = Some(SpanDepth(span_depth));
+        }
+        if !control.root && self.exceeds_max_children(attrs, &ctx) {
+            return;
+        }
 
-            if let Some(line) = meta.line() {
-                props.push(("code.lineno", line.to_string()));
+        let name = match control.name {
+            Some(name) => Cow::Owned(name),
+            None => Cow::Borrowed(attrs.metadata().name()),
+        };
+        insert_inherited(&span, baggage, tenant, depth);
+
+        if let Some(sink) = capture::current_sink() {
+            let parent_id = self
+                .parent_span(attrs, &ctx)
+                .filter(|_| !control.root)
+                .and_then(|parent| {
+                    parent.scope().find_map(|span| {
+                        span.extensions()
+                            .get::<CapturingSpan>()
+                            .map(CapturingSpan::id)
+                    })
+                });
+            if let Some(lazy) = lazy {
+                properties.extend(self.lazy_properties(lazy));
             }
+            #[cfg(feature = "tokio")]
+            spawn::spawn_origin_properties(&mut properties);
+            let capturing = CapturingSpan::new(sink, name, id.into_u64(), parent_id, properties);
+            let mut extensions = span.extensions_mut();
+            extensions.insert(capturing);
+            extensions.insert(fastrace::Span::noop());
+            return;
         }
 
-        if self.with_threads {
-            THREAD_ID.with(|id| {
-                props.push(("thread.id", id.to_string()));
-            });
-            if let Some(name) = std::thread::current().name() {
-                props.push(("thread.name", name.to_string()));
+        if self.is_shadow_mode() {
+            self.count_span();
+            span.extensions_mut().insert(fastrace::Span::noop());
+            return;
+        }
+
+        let early = self
+            .early_buffer
+            .as_ref()
+            .filter(|buffer| buffer.is_buffering())
+            .map(|buffer| (buffer, self.early_parent(attrs, &ctx, control.root)));
+        if let Some((buffer, parent)) = early {
+            if let Some(lazy) = lazy {
+                properties.extend(self.lazy_properties(lazy));
             }
+            #[cfg(feature = "tokio")]
+            spawn::spawn_origin_properties(&mut properties);
+            let mut extensions = span.extensions_mut();
+            let Some(pending) = buffer.new_pending_span(name, parent, properties) else {
+                extensions.insert(fastrace::Span::noop());
+                return;
+            };
+            extensions.insert(pending);
+            extensions.insert(EarlySpan);
+            return;
         }
 
-        if self.with_level {
-            props.push(("level", attrs.metadata().level().to_string()));
+        let orphan = self
+            .orphan_buffer
+            .as_ref()
+            .filter(|_| !control.root)
+            .and_then(|buffer| Some((buffer, self.orphan_parent(buffer, attrs, &ctx)?)));
+        if let Some((buffer, parent)) = orphan {
+            if let Some(lazy) = lazy {
+                properties.extend(self.lazy_properties(lazy));
+            }
+            #[cfg(feature = "tokio")]
+            spawn::spawn_origin_properties(&mut properties);
+            let pending = buffer.new_pending_span(name, parent, properties);
+            span.extensions_mut().insert(pending);
+            return;
         }
 
-        fastrace_span = fastrace_span.with_properties(|| props);
+        let in_flight = match &self.spans_in_flight {
+            Some(spans_in_flight) => match spans_in_flight.acquire() {
+                Some(guard) => Some(guard),
+                None => {
+                    match spans_in_flight.policy() {
+                        InFlightPolicy::DropNew => {}
+                        InFlightPolicy::Event => self.record_span_as_event(attrs, &span, &ctx),
+                        InFlightPolicy::Noop => {
+                            span.extensions_mut().insert(fastrace::Span::noop());
+                        }
+                    }
+                    return;
+                }
+            },
+            None => None,
+        };
 
-        attrs.record(&mut SpanAttributeVisitor {
-            fastrace_span: &mut fastrace_span,
+        let segment_name = self.span_segmentation.map(|_| name.clone());
+        let fastrace_span = if control.root {
+            self.new_root_span(name, SpanContext::random(), attrs.metadata())
+        } else {
+            self.new_fastrace_span(attrs, &ctx, name, &properties)
+        };
+        if let Some(rate) = control.sample_rate {
+            if !is_sampled(&fastrace_span, rate) {
+                fastrace_span.cancel();
+                return;
+            }
+        }
+        // The origin of a spawned task is recorded on the first span that is actually bridged.
+        #[cfg(feature = "tokio")]
+        spawn::spawn_origin_properties(&mut properties);
+        let error = self.recorded_error(&properties);
+        let segments = segment_name.and_then(|name| {
+            let context = SpanContext::from_span(&fastrace_span)?;
+            let parent = if control.root {
+                None
+            } else {
+                self.parent_context(attrs, &ctx)
+            };
+            let parent = parent.unwrap_or(SpanContext::new(context.trace_id, SpanId(0)));
+            Some(SpanSegments::new(name, parent, properties.clone()))
+        });
+        let summary = self.close_summary.as_ref().map(|keys| {
+            let mut summary = CloseSummary::default();
+            summary.capture(keys, &properties);
+            summary
         });
+        let mut budget = self
+            .span_memory_budget
+            .map(|(limit, policy)| SpanBudget::new(limit, policy));
+        if let Some(budget) = &mut budget {
+            budget.admit_properties(&mut properties);
+        }
+        let fastrace_span = fastrace_span.with_properties(|| properties);
+        let sampled_trace = self.adaptive_sampler.is_some()
+            && SpanContext::from_span(&fastrace_span)
+                .is_some_and(|context| self.is_sampled_trace(attrs, &ctx, context));
 
         let mut extensions = span.extensions_mut();
+        if let Some(budget) = budget {
+            extensions.insert(budget);
+        }
+        if let Some(summary) = summary {
+            extensions.insert(summary);
+        }
+        if let Some(segments) = segments {
+            extensions.insert(segments);
+        }
+        if let Some(in_flight) = in_flight {
+            extensions.insert(in_flight);
+        }
+        if let Some(lazy) = lazy.filter(|lazy| !lazy.is_empty()) {
+            extensions.insert(lazy);
+        }
+        if let Some(message) = error {
+            get_or_insert_default::<SpanErrors>(&mut extensions).message = Some(message);
+        }
+        if let Some(deadlines) = &self.deadlines {
+            let context = SpanContext::from_span(&fastrace_span);
+            extensions.insert(deadlines.start(id.clone(), context));
+        }
+        if sampled_trace {
+            if let Some(sampler) = &self.adaptive_sampler {
+                sampler.record_span();
+            }
+            extensions.insert(SampledTrace);
+        }
+        self.count_span();
         extensions.insert(fastrace_span);
     }
 
     fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if self.ignore_span_fields {
+            return;
+        }
+        let _holding = reentrancy::hold_extensions();
         let span = ctx.span(id).expect("Span not found, this is a bug");
         let mut extension = span.extensions_mut();
-        let Some(fastrace_span) = extension.get_mut::<fastrace::Span>() else {
+        let mut properties = Vec::new();
+        let mut baggage = Baggage::new();
+        let mut control = ControlFields::default();
+        values.record(&mut SpanAttributeVisitor {
+            properties: &mut properties,
+            baggage: &mut baggage,
+            control: &mut control,
+            lazy: None,
+            drop_if: None,
+            options: self.field_options,
+        });
+        #[cfg(feature = "fmt")]
+        if let Some(fields) = self
+            .fields_formatter
+            .as_ref()
+            .and_then(|formatter| formatter.format_record(values))
+        {
+            properties.push((FIELD_FORMATTED_FIELDS.into(), fields.into()));
+        }
+        properties.extend(baggage.properties());
+        self.transform_properties(&mut properties);
+
+        if let Some(name) = control.name {
+            match extension.get_mut::<PendingSpan>() {
+                Some(pending) => pending.rename(name),
+                // fastrace cannot rename a span once it has started.
+                None => properties.push((FIELD_FASTRACE_NAME.into(), name.into())),
+            }
+        }
+
+        let error = self.recorded_error(&properties);
+        if let Some(keys) = &self.close_summary {
+            if let Some(summary) = extension.get_mut::<CloseSummary>() {
+                summary.capture(keys, &properties);
+            }
+        }
+        if let Some(budget) = extension.get_mut::<SpanBudget>() {
+            budget.admit_properties(&mut properties);
+        }
+        if let Some(capturing) = extension.get_mut::<CapturingSpan>() {
+            capturing.add_properties(properties);
+        } else if let Some(fastrace_span) = extension.get_mut::<fastrace::Span>() {
+            fastrace_span.add_properties(|| properties);
+            if let Some(message) = error {
+                get_or_insert_default::<SpanErrors>(&mut extension).message = Some(message);
+            }
+        } else if let Some(pending) = extension.get_mut::<PendingSpan>() {
+            pending.add_properties(properties);
+        } else {
+            let event_only = extension.get_mut::<EventOnlySpan>().is_some();
+            let ended = extension.get_mut::<EndedSpan>().is_some();
+            drop(extension);
+            if properties.is_empty() {
+                return;
+            }
+            match self.late_records {
+                // Fields recorded after the span was recorded as an event are recorded as another
+                // event, e.g., the status of an HTTP client request.
+                _ if event_only => {}
+                LateRecords::Event if ended => {}
+                LateRecords::Callback(callback) if ended => {
+                    callback(span.metadata(), &properties);
+                    return;
+                }
+                _ => return,
+            }
+            let fastrace_event = fastrace::Event::new(span.name()).with_properties(|| properties);
+            if let Some(ancestor) = span
+                .parent()
+                .and_then(|parent| find_recorded_ancestor(&parent))
+            {
+                add_events(&ancestor, vec![fastrace_event]);
+            }
             return;
-        };
-        values.record(&mut SpanAttributeVisitor { fastrace_span });
+        }
+
+        if !baggage.is_empty() {
+            match extension.get_mut::<Baggage>() {
+                Some(existing) => existing.extend(baggage),
+                None => extension.insert(baggage),
+            }
+        }
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        match id {
+            id if id == TypeId::of::<Self>() => Some(self as *const _ as *const ()),
+            id if id == TypeId::of::<WithContext>() => {
+                Some(&self.get_context as *const _ as *const ())
+            }
+            _ => None,
+        }
+    }
+
+    fn on_exit(&self, _id: &Id, _ctx: Context<'_, S>) {
+        if let Some(buffer) = &self.orphan_buffer {
+            buffer.flush_to_local_parent();
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let _holding = reentrancy::hold_extensions();
+        if let Some(buffer) = &self.orphan_buffer {
+            buffer.flush_to_local_parent();
+        }
+
+        let span = ctx.span(&id).expect("Span not found, this is a bug");
+        let mut extensions = span.extensions_mut();
+
+        if let Some(deadlines) = extensions.remove::<SpanDeadlines>() {
+            if let Some(scheduler) = &self.deadlines {
+                scheduler.finish(&deadlines);
+            }
+        }
+
+        if let Some(aggregated) = extensions.remove::<AggregatedSpan>() {
+            drop(extensions);
+            if let Some(parent) = span
+                .parent()
+                .and_then(|parent| find_recorded_ancestor(&parent))
+            {
+                let mut extensions = parent.extensions_mut();
+                get_or_insert_default::<SpanAggregates>(&mut extensions)
+                    .push(span.name(), aggregated.elapsed());
+            }
+            return;
+        }
+
+        if let Some(pending) = extensions.remove::<PendingSpan>() {
+            if extensions.remove::<EarlySpan>().is_some() {
+                if let Some(buffer) = &self.early_buffer {
+                    buffer.push(pending);
+                }
+            } else if let Some(buffer) = &self.orphan_buffer {
+                buffer.push(pending);
+            }
+            return;
+        }
+
+        if let Some(capturing) = extensions.remove::<CapturingSpan>() {
+            capturing.finish();
+            return;
+        }
+
+        self.record_lazy_fields(&mut extensions);
+
+        if let Some(batcher) = &self.event_batcher {
+            if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+                for event in batcher.take(&id) {
+                    fastrace_span.add_event(event);
+                }
+            }
+        }
+
+        if let Some(children) = extensions.remove::<ChildCount>() {
+            if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+                let dropped = children.dropped;
+                if dropped > 0 {
+                    fastrace_span.add_property(|| ("children.dropped", dropped.to_string()));
+                }
+            }
+        }
+
+        if let Some(budget) = extensions.remove::<SpanBudget>() {
+            if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+                budget.finish(fastrace_span);
+            }
+        }
+
+        if let Some(aggregates) = extensions.remove::<SpanAggregates>() {
+            if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+                aggregates.emit(fastrace_span);
+            }
+        }
+
+        if let Some(errors) = extensions.remove::<SpanErrors>() {
+            if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+                let mut properties: Vec<Property> = Vec::with_capacity(3);
+                if let Some(message) = errors.message {
+                    properties.push((FIELD_EXCEPTION_MESSAGE.into(), message.into()));
+                }
+                properties.push(("error.count".into(), errors.events.to_string().into()));
+                if let Some(duration) = fastrace_span.elapsed() {
+                    properties.push(("duration_ns".into(), duration.as_nanos().to_string().into()));
+                }
+                let summary = fastrace::Event::new("error.summary").with_properties(|| properties);
+                fastrace_span.add_event(summary);
+            }
+        }
+
+        if let Some(summary) = extensions.remove::<CloseSummary>() {
+            if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+                summary.emit(span.name(), fastrace_span);
+            }
+        }
+
+        if let Some((_, EventFolding::Count)) = self.event_folding {
+            if let Some(folded) = extensions.remove::<FoldedEvents>() {
+                if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+                    fastrace_span.add_properties(|| {
+                        folded.counts.into_iter().map(|(level, count)| {
+                            let key = format!("log.{}.count", level_name(level));
+                            (key, count.to_string())
+                        })
+                    });
+                }
+            }
+        }
     }
 
     fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
-        // Ignore events that are not in the context of a span
-        if let Some(span) = event.parent().and_then(|id| ctx.span(id)).or_else(|| {
+        if self.is_shut_down()
+            || reentrancy::is_suppressed()
+            || !self.is_event_enabled(event.metadata())
+        {
+            return;
+        }
+        let _guard = reentrancy::suppress();
+        let _holding = reentrancy::hold_extensions();
+
+        let _measurement = self
+            .self_telemetry
+            .as_ref()
+            .map(|telemetry| telemetry.measure(Callback::Event));
+
+        self.check_deadlines(&ctx);
+
+        let span = event.parent().and_then(|id| ctx.span(id)).or_else(|| {
             event
                 .is_contextual()
                 .then(|| ctx.lookup_current())
                 .flatten()
-        }) {
-            let mut extensions = span.extensions_mut();
-            let fastrace_span = extensions.get_mut::<fastrace::Span>();
-
-            if let Some(fastrace_span) = fastrace_span {
-                let mut name_finder = EventNameFinder { name: None };
-                event.record(&mut name_finder);
-                let event_name = name_finder
-                    .name
-                    .unwrap_or_else(|| Cow::Borrowed(event.metadata().name()));
-
-                let mut fastrace_event = fastrace::Event::new(event_name).with_properties(|| {
-                    [
-                        ("level", event.metadata().level().as_str().to_string()),
-                        ("target", event.metadata().target().to_string()),
-                    ]
-                });
+        });
 
-                if self.location {
-                    if let Some(file) = event.metadata().file() {
-                        fastrace_event =
-                            fastrace_event.with_property(|| ("code.filepath", file.to_string()));
-                    }
-                    if let Some(module) = event.metadata().module_path() {
-                        fastrace_event =
-                            fastrace_event.with_property(|| ("code.namespace", module.to_string()));
+        if let Some(bridge) = &self.event_to_span_bridge {
+            if self.bridge_event_pair(bridge, event, span.as_ref()) {
+                return;
+            }
+        }
+
+        // Record on the fastrace local parent right away if nothing needs the bridged span.
+        if !self.inspects_events()
+            && !self.events_as_spans
+            && !is_marked_as_span(event)
+            && (self.return_capture.is_none() || InstrumentOutput::of(event.metadata()).is_none())
+            && self.add_local_parent_event(event, span.as_ref())
+        {
+            return;
+        }
+
+        // Ignore events that are not in the context of a span
+        let Some(span) = span else {
+            if self.add_local_parent_event(event, None) {
+                return;
+            }
+            #[cfg(feature = "log")]
+            self.log_dropped_event(event);
+            return;
+        };
+
+        if let Some(recorded) = find_recorded_ancestor(&span) {
+            if self.lazy_fields {
+                self.record_lazy_fields(&mut recorded.extensions_mut());
+            }
+
+            let output = self
+                .return_capture
+                .and_then(|returns| Some((returns, InstrumentOutput::of(event.metadata())?)));
+            let as_span = self.events_as_spans || is_marked_as_span(event);
+            if let Some(batcher) = self
+                .event_batcher()
+                .filter(|_| output.is_none() && !as_span)
+            {
+                let mut properties = self.convert_event_properties(event, Some(&span));
+                properties.push(event_time_now());
+                let fastrace_event =
+                    fastrace::Event::new(self.event_name(event)).with_properties(|| properties);
+                self.count_event();
+                for (id, events) in batcher.push(&recorded.id(), fastrace_event) {
+                    if let Some(span) = ctx.span(&id) {
+                        add_events(&span, events);
                     }
-                    if let Some(line) = event.metadata().line() {
-                        fastrace_event =
-                            fastrace_event.with_property(|| ("code.lineno", line.to_string()));
+                }
+                return;
+            }
+
+            let mut extensions = recorded.extensions_mut();
+            let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() else {
+                return;
+            };
+
+            match output {
+                Some((returns, InstrumentOutput::Return)) => {
+                    if let Some(value) = returns.capture(InstrumentOutput::value(event)) {
+                        let mut properties = vec![("return.value".into(), value)];
+                        self.transform_properties(&mut properties);
+                        fastrace_span.add_properties(|| properties);
                     }
+                    return;
+                }
+                Some((_, InstrumentOutput::Error)) => {
+                    let mut properties = vec![
+                        ("status.code".into(), "ERROR".into()),
+                        ("status.message".into(), InstrumentOutput::value(event)),
+                    ];
+                    self.transform_properties(&mut properties);
+                    fastrace_span.add_properties(|| properties);
                 }
+                None => {}
+            }
 
-                event.record(&mut EventVisitor {
-                    fastrace_event: &mut fastrace_event,
-                });
+            let event_name = self.event_name(event);
 
-                fastrace_span.add_event(fastrace_event);
+            let level = *event.metadata().level();
+            if level == Level::ERROR {
+                if let Some(summary) = extensions.get_mut::<CloseSummary>() {
+                    summary.fail();
+                }
             }
-        };
+            if let Some(deadlines) = extensions.get_mut::<SpanDeadlines>() {
+                deadlines.record_event();
+            }
+            if self.error_summary && level == Level::ERROR {
+                let errors = get_or_insert_default::<SpanErrors>(&mut extensions);
+                errors.events += 1;
+                if errors.message.is_none() {
+                    errors.message = Some(event_name.to_string());
+                }
+            }
+
+            if let Some((threshold, folding)) = self.event_folding {
+                if level > threshold {
+                    let index =
+                        get_or_insert_default::<FoldedEvents>(&mut extensions).increment(level);
+
+                    if folding == EventFolding::Properties {
+                        if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+                            let key = format!("log.{}.{}", level_name(level), index);
+                            fastrace_span.add_property(|| (key, event_name));
+                        }
+                    }
+                    return;
+                }
+            }
+
+            let properties = self.convert_event_properties(event, Some(&span));
+            if let Some(capturing) = extensions.get_mut::<CapturingSpan>() {
+                capturing.add_event(event_name, properties);
+                return;
+            }
+            if as_span {
+                if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+                    let _span = fastrace::Span::enter_with_parent(event_name, fastrace_span)
+                        .with_properties(|| properties);
+                }
+                return;
+            }
+            let fastrace_event = match extensions.get_mut::<SpanBudget>() {
+                Some(budget) => budget.admit_event(event_name, properties),
+                None => Some(fastrace::Event::new(event_name).with_properties(|| properties)),
+            };
+            if let Some(fastrace_event) = fastrace_event {
+                self.count_event();
+                if let Some(fastrace_span) = extensions.get_mut::<fastrace::Span>() {
+                    fastrace_span.add_event(fastrace_event);
+                }
+            }
+            self.segment_span(&mut extensions);
+        } else if let Some(pending) = (self.orphan_buffer.is_some() || self.early_buffer.is_some())
+            .then(|| {
+                span.scope()
+                    .find(|span| span.extensions().get::<PendingSpan>().is_some())
+            })
+            .flatten()
+        {
+            let fastrace_event = self.convert_event(self.event_name(event), event, Some(&span));
+            let mut extensions = pending.extensions_mut();
+            if let Some(pending) = extensions.get_mut::<PendingSpan>() {
+                pending.add_event(fastrace_event);
+            }
+        } else if !self.add_local_parent_event(event, Some(&span)) {
+            #[cfg(feature = "log")]
+            self.log_dropped_event(event);
+        }
     }
 }
@@ -0,0 +1,28 @@
+use std::borrow::Cow;
+
+use tracing_core::Metadata;
+
+/// Describes how the roots the layer creates for spans without any parent are named, configured
+/// with
+/// [`FastraceCompatLayer::with_implicit_root_naming`](crate::FastraceCompatLayer::with_implicit_root_naming).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum RootNaming {
+    /// Roots are named after their span, as is.
+    #[default]
+    Verbatim,
+    /// Roots are named `<target>/<name>`, e.g., `hyper::client/connect`.
+    TargetAndName,
+    /// Roots are named after their span, preceded by the given prefix, e.g., `orphan:connect`.
+    Prefix(Cow<'static, str>),
+}
+
+impl RootNaming {
+    /// Returns the name of an implicit root for the span `name` with the given metadata.
+    pub(crate) fn name(&self, name: Cow<'static, str>, meta: &Metadata<'_>) -> Cow<'static, str> {
+        match self {
+            RootNaming::Verbatim => name,
+            RootNaming::TargetAndName => format!("{}/{}", meta.target(), name).into(),
+            RootNaming::Prefix(prefix) => format!("{}{}", prefix, name).into(),
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use fastrace::prelude::SpanContext;
+
+use crate::Property;
+
+/// A tracing span created before any fastrace root existed, or before fastrace reporting was
+/// live.
+///
+/// Pending spans are not materialized as fastrace spans right away. Their properties and events
+/// are kept in the registry extensions until the span closes, and then moved to the
+/// [`OrphanBuffer`] or the [`EarlyBuffer`](crate::early::EarlyBuffer).
+pub(crate) struct PendingSpan {
+    seq: u64,
+    parent: Option<u64>,
+    name: Cow<'static, str>,
+    properties: Vec<Property>,
+    events: Vec<fastrace::Event>,
+    start_time: SystemTime,
+    start: Instant,
+    duration: Duration,
+}
+
+impl PendingSpan {
+    pub(crate) fn new(
+        seq: u64,
+        parent: Option<u64>,
+        name: Cow<'static, str>,
+        properties: Vec<Property>,
+    ) -> Self {
+        PendingSpan {
+            seq,
+            parent,
+            name,
+            properties,
+            events: Vec::new(),
+            start_time: SystemTime::now(),
+            start: Instant::now(),
+            duration: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    pub(crate) fn parent(&self) -> Option<u64> {
+        self.parent
+    }
+
+    /// Records the duration of the span, once it has closed.
+    pub(crate) fn close(&mut self) {
+        self.duration = self.start.elapsed();
+    }
+
+    pub(crate) fn rename(&mut self, name: String) {
+        self.name = Cow::Owned(name);
+    }
+
+    pub(crate) fn add_properties(&mut self, properties: impl IntoIterator<Item = Property>) {
+        self.properties.extend(properties);
+    }
+
+    pub(crate) fn add_event(&mut self, event: fastrace::Event) {
+        self.events.push(event);
+    }
+
+    fn into_fastrace_span(self, fastrace_span: fastrace::Span) -> fastrace::Span {
+        let start_unix_ns = self
+            .start_time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let fastrace_span = fastrace_span
+            .with_properties(|| self.properties)
+            .with_properties(|| {
+                [
+                    ("buffered.start_unix_ns", start_unix_ns.to_string()),
+                    ("buffered.duration_ns", self.duration.as_nanos().to_string()),
+                ]
+            });
+        for event in self.events {
+            fastrace_span.add_event(event);
+        }
+        fastrace_span
+    }
+}
+
+/// Holds the orphan spans that closed before any fastrace root appeared.
+pub(crate) struct OrphanBuffer {
+    capacity: usize,
+    next_seq: AtomicU64,
+    root_seen: AtomicBool,
+    len: AtomicUsize,
+    state: Mutex<OrphanState>,
+}
+
+#[derive(Default)]
+struct OrphanState {
+    /// The spans that are still open.
+    open: HashSet<u64>,
+    /// The spans that have closed, but have not been emitted yet.
+    closed: Vec<PendingSpan>,
+    /// The fastrace local parent the buffered spans are emitted under, once one has appeared.
+    parent: Option<SpanContext>,
+}
+
+impl OrphanBuffer {
+    pub(crate) fn new(capacity: usize) -> Self {
+        OrphanBuffer {
+            capacity,
+            next_seq: AtomicU64::new(0),
+            root_seen: AtomicBool::new(false),
+            len: AtomicUsize::new(0),
+            state: Mutex::new(OrphanState::default()),
+        }
+    }
+
+    /// Returns `true` if spans without a fastrace parent should still be buffered, i.e., no
+    /// fastrace root has been seen yet.
+    pub(crate) fn is_buffering(&self) -> bool {
+        !self.root_seen.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn new_pending_span(
+        &self,
+        name: Cow<'static, str>,
+        parent: Option<u64>,
+        properties: Vec<Property>,
+    ) -> PendingSpan {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        self.lock().open.insert(seq);
+        PendingSpan::new(seq, parent, name, properties)
+    }
+
+    /// Buffers a closed orphan span, and emits the buffered spans if a fastrace local parent
+    /// has appeared.
+    ///
+    /// If the buffer is full while no fastrace root has appeared, the span is recorded as a new
+    /// root instead.
+    pub(crate) fn push(&self, mut pending: PendingSpan) {
+        pending.close();
+        {
+            let mut state = self.lock();
+            state.open.remove(&pending.seq);
+            if state.parent.is_none() && state.closed.len() >= self.capacity {
+                drop(state);
+                let root = fastrace::Span::root(pending.name.clone(), SpanContext::random());
+                drop(pending.into_fastrace_span(root));
+                return;
+            }
+            state.closed.push(pending);
+            self.len.store(state.closed.len(), Ordering::Relaxed);
+        }
+        self.flush_to_local_parent();
+    }
+
+    /// Emits the buffered spans as children of the fastrace local parent that appeared first.
+    ///
+    /// The first time this is called while there is a fastrace local parent, that parent is
+    /// remembered, so that spans closing afterwards join the same trace. Spans with an open
+    /// ancestor are held back until the ancestor closes, so that they stay nested under it.
+    pub(crate) fn flush_to_local_parent(&self) {
+        if self.len.load(Ordering::Relaxed) == 0 && !self.is_buffering() {
+            return;
+        }
+
+        let (ready, parent) = {
+            let mut state = self.lock();
+            let parent = match state.parent {
+                Some(parent) => parent,
+                None => {
+                    let Some(parent) = SpanContext::current_local_parent() else {
+                        return;
+                    };
+                    self.root_seen.store(true, Ordering::Relaxed);
+                    *state.parent.insert(parent)
+                }
+            };
+
+            let mut pending = std::mem::take(&mut state.closed);
+            pending.sort_by_key(PendingSpan::seq);
+
+            // Parents are created before their children, so they come first.
+            let mut held = HashSet::new();
+            let (held_back, ready): (Vec<_>, Vec<_>) = pending.into_iter().partition(|span| {
+                let is_held = span
+                    .parent
+                    .is_some_and(|parent| state.open.contains(&parent) || held.contains(&parent));
+                if is_held {
+                    held.insert(span.seq);
+                }
+                is_held
+            });
+            state.closed = held_back;
+            self.len.store(state.closed.len(), Ordering::Relaxed);
+            (ready, parent)
+        };
+        emit_pending_spans(ready, |name| fastrace::Span::root(name, parent));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, OrphanState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Emits closed pending spans as fastrace spans, nested as they were in tracing.
+///
+/// Spans whose parent is not among `pending` are created with `new_root`.
+pub(crate) fn emit_pending_spans(
+    mut pending: Vec<PendingSpan>,
+    new_root: impl Fn(Cow<'static, str>) -> fastrace::Span,
+) {
+    pending.sort_by_key(|span| span.seq);
+
+    let mut emitted: Vec<(u64, fastrace::Span)> = Vec::with_capacity(pending.len());
+    for span in pending {
+        let parent = span
+            .parent
+            .and_then(|parent| emitted.iter().find(|(seq, _)| *seq == parent));
+        let fastrace_span = match parent {
+            Some((_, parent)) => fastrace::Span::enter_with_parent(span.name.clone(), parent),
+            None => new_root(span.name.clone()),
+        };
+        let seq = span.seq;
+        emitted.push((seq, span.into_fastrace_span(fastrace_span)));
+    }
+
+    // End children before their parents.
+    while emitted.pop().is_some() {}
+}
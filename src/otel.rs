@@ -0,0 +1,111 @@
+use fastrace::prelude::SpanContext;
+use fastrace::prelude::SpanId;
+use fastrace::prelude::TraceId;
+use opentelemetry::Context;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::propagation::Injector;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry::trace::TraceFlags;
+use opentelemetry::trace::TraceState;
+#[cfg(feature = "tracing-opentelemetry")]
+use tracing_opentelemetry::OtelData;
+#[cfg(feature = "tracing-opentelemetry")]
+use tracing_subscriber::registry::Extensions;
+
+#[cfg(feature = "tracing-opentelemetry")]
+use crate::Property;
+
+/// Adapts an OpenTelemetry [`TextMapPropagator`] to fastrace span contexts, so that existing
+/// propagator stacks, e.g., a composite of W3C trace context and a vendor format, can be reused
+/// to produce the remote parent of bridged spans.
+///
+/// The extracted context is typically used as the parent of a fastrace root span set as the
+/// local parent, which the spans of the request are then attached to. Requires the
+/// `opentelemetry` feature.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use fastrace::prelude::*;
+/// use fastrace_tracing::OtelPropagator;
+/// use opentelemetry::propagation::TextMapPropagator;
+///
+/// fn handle(
+///     propagator: &OtelPropagator<impl TextMapPropagator>,
+///     headers: &HashMap<String, String>,
+/// ) {
+///     let parent = propagator
+///         .extract(headers)
+///         .unwrap_or_else(SpanContext::random);
+///     let root = Span::root("request", parent);
+///     let _guard = root.set_local_parent();
+///
+///     // Spans created with `tracing` are now attached to the remote parent.
+/// }
+/// ```
+pub struct OtelPropagator<P> {
+    inner: P,
+}
+
+impl<P: TextMapPropagator> OtelPropagator<P> {
+    /// Creates an [`OtelPropagator`] adapting `inner`.
+    pub fn new(inner: P) -> Self {
+        OtelPropagator { inner }
+    }
+
+    /// Extracts the remote span context carried by `extractor`, if any is valid.
+    pub fn extract(&self, extractor: &dyn Extractor) -> Option<SpanContext> {
+        let context = self.inner.extract(extractor);
+        let span = context.span();
+        let remote = span.span_context();
+        if !remote.is_valid() {
+            return None;
+        }
+
+        let trace_id = TraceId(u128::from_be_bytes(remote.trace_id().to_bytes()));
+        let span_id = SpanId(u64::from_be_bytes(remote.span_id().to_bytes()));
+        Some(SpanContext::new(trace_id, span_id).sampled(remote.is_sampled()))
+    }
+
+    /// Injects `span_context` into `injector`.
+    pub fn inject(&self, span_context: SpanContext, injector: &mut dyn Injector) {
+        let trace_flags = if span_context.sampled {
+            TraceFlags::SAMPLED
+        } else {
+            TraceFlags::default()
+        };
+        let remote = opentelemetry::trace::SpanContext::new(
+            opentelemetry::trace::TraceId::from_bytes(span_context.trace_id.0.to_be_bytes()),
+            opentelemetry::trace::SpanId::from_bytes(span_context.span_id.0.to_be_bytes()),
+            trace_flags,
+            true,
+            TraceState::default(),
+        );
+        let context = Context::new().with_remote_span_context(remote);
+        self.inner.inject_context(&context, injector);
+    }
+
+    /// Injects the span context of the current fastrace local parent into `injector`, if any.
+    pub fn inject_current(&self, injector: &mut dyn Injector) {
+        if let Some(span_context) = SpanContext::current_local_parent() {
+            self.inject(span_context, injector);
+        }
+    }
+}
+
+/// Returns the name and attributes that `tracing-opentelemetry` recorded for a span, if its layer
+/// has seen the span.
+#[cfg(feature = "tracing-opentelemetry")]
+pub(crate) fn otel_data(extensions: &Extensions<'_>) -> Option<(String, Vec<Property>)> {
+    let builder = &extensions.get::<OtelData>()?.builder;
+    let attributes = builder
+        .attributes
+        .iter()
+        .flatten()
+        .map(|kv| (kv.key.to_string().into(), kv.value.to_string().into()))
+        .collect();
+    Some((builder.name.to_string(), attributes))
+}
@@ -0,0 +1,85 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use tracing_core::Event;
+use tracing_core::Metadata;
+use tracing_core::field;
+
+use crate::budget::truncate_value;
+
+/// The field of the event `#[instrument(ret)]` records the return value in.
+const FIELD_RETURN: &str = "return";
+/// The field of the event `#[instrument(err)]` records the returned error in.
+const FIELD_ERROR: &str = "error";
+
+/// Describes how the return values recorded by `#[instrument(ret)]` are captured, configured
+/// with
+/// [`FastraceCompatLayer::with_instrument_output`](crate::FastraceCompatLayer::with_instrument_output).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReturnCapture {
+    /// Return values are not recorded.
+    Skip,
+    /// Return values are recorded in full.
+    Full,
+    /// Return values are truncated to the given number of bytes.
+    Truncate(usize),
+}
+
+/// The output of an `#[instrument]`ed function, recorded as an event with a single field.
+pub(crate) enum InstrumentOutput {
+    Return,
+    Error,
+}
+
+impl InstrumentOutput {
+    /// Returns the kind of output the event with the given metadata records, if any.
+    pub(crate) fn of(meta: &Metadata<'_>) -> Option<Self> {
+        let fields = meta.fields();
+        if fields.len() != 1 {
+            return None;
+        }
+        if fields.field(FIELD_RETURN).is_some() {
+            Some(InstrumentOutput::Return)
+        } else if fields.field(FIELD_ERROR).is_some() {
+            Some(InstrumentOutput::Error)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the recorded value of an output event.
+    pub(crate) fn value(event: &Event<'_>) -> Cow<'static, str> {
+        let mut visitor = OutputVisitor { value: None };
+        event.record(&mut visitor);
+        Cow::Owned(visitor.value.unwrap_or_default())
+    }
+}
+
+impl ReturnCapture {
+    /// Applies the capture mode to a return value, returning `None` if it should not be recorded.
+    pub(crate) fn capture(self, mut value: Cow<'static, str>) -> Option<Cow<'static, str>> {
+        match self {
+            ReturnCapture::Skip => None,
+            ReturnCapture::Full => Some(value),
+            ReturnCapture::Truncate(max_len) => {
+                truncate_value(&mut value, max_len);
+                Some(value)
+            }
+        }
+    }
+}
+
+/// Finds the value of the single field of an output event.
+struct OutputVisitor {
+    value: Option<String>,
+}
+
+impl field::Visit for OutputVisitor {
+    fn record_str(&mut self, _field: &field::Field, value: &str) {
+        self.value = Some(value.to_string());
+    }
+
+    fn record_debug(&mut self, _field: &field::Field, value: &dyn fmt::Debug) {
+        self.value = Some(format!("{:?}", value));
+    }
+}
@@ -0,0 +1,156 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use tracing_core::Event;
+use tracing_core::field;
+
+/// The maximum number of paired spans kept open at once, so that start events whose end event
+/// never comes do not accumulate forever.
+const MAX_OPEN_SPANS: usize = 10_000;
+
+/// A pair of events marking the start and the end of an operation.
+struct EventPair {
+    name: Cow<'static, str>,
+    start: Cow<'static, str>,
+    end: Cow<'static, str>,
+    correlation_field: Cow<'static, str>,
+}
+
+/// Whether an event starts or ends an operation.
+pub(crate) enum Edge {
+    Start,
+    End,
+}
+
+/// Synthesizes fastrace spans from pairs of "started"/"finished" events, for libraries that emit
+/// such events rather than spans.
+///
+/// The bridge is installed with
+/// [`FastraceCompatLayer::with_event_to_span_bridge`](crate::FastraceCompatLayer::with_event_to_span_bridge).
+/// Events are matched by their message, and a start event is paired with the end event carrying
+/// the same value in the correlation field. The span covers the interval between both events,
+/// carries the properties of both, and is a child of the span of the start event. Paired events
+/// are not recorded as events. End events without a matching start event are recorded as usual.
+///
+/// # Example
+///
+/// ```
+/// use fastrace_tracing::EventToSpanBridge;
+/// use fastrace_tracing::FastraceCompatLayer;
+///
+/// let bridge = EventToSpanBridge::new().with_pair(
+///     "compaction",
+///     "compaction started",
+///     "compaction finished",
+///     "job_id",
+/// );
+/// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+///     .with_event_to_span_bridge(bridge);
+///
+/// tracing::info!(job_id = 42, "compaction started");
+/// tracing::info!(job_id = 42, "compaction finished");
+/// ```
+#[derive(Default)]
+pub struct EventToSpanBridge {
+    pairs: Vec<EventPair>,
+    open: Mutex<HashMap<(usize, String), fastrace::Span>>,
+}
+
+impl EventToSpanBridge {
+    /// Creates an [`EventToSpanBridge`] without any pair.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a pair of events, whose messages are `start` and `end`, correlated by the value of
+    /// `correlation_field` and synthesized into a span named `name`.
+    pub fn with_pair(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        start: impl Into<Cow<'static, str>>,
+        end: impl Into<Cow<'static, str>>,
+        correlation_field: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.pairs.push(EventPair {
+            name: name.into(),
+            start: start.into(),
+            end: end.into(),
+            correlation_field: correlation_field.into(),
+        });
+        self
+    }
+
+    /// Returns the pair `event` belongs to, whether it starts or ends the operation, and its
+    /// correlation value.
+    pub(crate) fn match_event(&self, event: &Event<'_>) -> Option<(usize, Edge, String)> {
+        let message = crate::event_name(event);
+        let (index, pair, edge) = self.pairs.iter().enumerate().find_map(|(index, pair)| {
+            if message == pair.start {
+                Some((index, pair, Edge::Start))
+            } else if message == pair.end {
+                Some((index, pair, Edge::End))
+            } else {
+                None
+            }
+        })?;
+
+        let mut finder = FieldValueFinder {
+            name: &pair.correlation_field,
+            value: None,
+        };
+        event.record(&mut finder);
+        Some((index, edge, finder.value?))
+    }
+
+    /// Returns the name of the spans synthesized for the pair `index`.
+    pub(crate) fn name(&self, index: usize) -> Cow<'static, str> {
+        self.pairs[index].name.clone()
+    }
+
+    /// Keeps `span` open until the end event of the pair `index` with the correlation value
+    /// `key` is recorded.
+    ///
+    /// Returns `false` if too many spans are open, in which case the span is cancelled.
+    pub(crate) fn start(&self, index: usize, key: String, span: fastrace::Span) -> bool {
+        let mut open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+        if open.len() >= MAX_OPEN_SPANS {
+            span.cancel();
+            return false;
+        }
+        // A start event repeated before the end event restarts the span.
+        if let Some(previous) = open.insert((index, key), span) {
+            previous.cancel();
+        }
+        true
+    }
+
+    /// Takes the span started for the pair `index` with the correlation value `key`, if any.
+    pub(crate) fn end(&self, index: usize, key: String) -> Option<fastrace::Span> {
+        self.open
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&(index, key))
+    }
+}
+
+/// Finds the value of a field by name among the fields of an event.
+struct FieldValueFinder<'a> {
+    name: &'a str,
+    value: Option<String>,
+}
+
+impl field::Visit for FieldValueFinder<'_> {
+    fn record_str(&mut self, field: &field::Field, value: &str) {
+        if field.name() == self.name {
+            self.value = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
+        if field.name() == self.name {
+            self.value = Some(format!("{:?}", value));
+        }
+    }
+}
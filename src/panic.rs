@@ -0,0 +1,73 @@
+use std::backtrace::Backtrace;
+use std::backtrace::BacktraceStatus;
+use std::panic::Location;
+
+use crate::FIELD_EXCEPTION_MESSAGE;
+use crate::FIELD_EXCEPTION_STACKTRACE;
+use crate::reentrancy;
+use crate::with_current_fastrace_span;
+
+/// Installs a panic hook recording panics as `ERROR` events on the current bridged span.
+///
+/// The event is named after the panic message and carries the `exception.message`,
+/// `exception.stacktrace`, `code.filepath`, and `code.lineno` properties. It is recorded before
+/// unwinding starts, so that crashed requests show the panic in their trace. If the current
+/// tracing span is not bridged, the event is recorded on the fastrace local parent, if any. So is
+/// it if the panic happens while the span is being updated, e.g., in a value encoder or a late
+/// records callback, as the span cannot be accessed then.
+///
+/// The backtrace is only captured if enabled with `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`, see
+/// [`Backtrace::capture`]. The previously installed hook is still called afterwards, e.g., to
+/// print the panic.
+///
+/// # Example
+///
+/// ```
+/// fastrace_tracing::install_panic_hook();
+/// ```
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        record_panic(message, info.location());
+
+        previous(info);
+    }));
+}
+
+#[cfg_attr(feature = "strip-location", allow(unused_mut, unused_variables))]
+fn record_panic(message: String, location: Option<&Location<'_>>) {
+    let mut properties = vec![
+        ("level", "ERROR".to_string()),
+        (FIELD_EXCEPTION_MESSAGE, message.clone()),
+    ];
+    let backtrace = Backtrace::capture();
+    if backtrace.status() == BacktraceStatus::Captured {
+        properties.push((FIELD_EXCEPTION_STACKTRACE, backtrace.to_string()));
+    }
+    #[cfg(not(feature = "strip-location"))]
+    if let Some(location) = location {
+        properties.push(("code.filepath", location.file().to_string()));
+        properties.push(("code.lineno", location.line().to_string()));
+    }
+    let event = fastrace::Event::new(message).with_properties(|| properties);
+
+    // Fall back to the local parent if the current tracing span is not bridged, or if its
+    // extensions may be locked by the panicking thread, which would deadlock.
+    let mut event = Some(event);
+    if !reentrancy::may_hold_extensions() {
+        with_current_fastrace_span(|fastrace_span| {
+            if let Some(event) = event.take() {
+                fastrace_span.add_event(event);
+            }
+        });
+    }
+    if let Some(event) = event {
+        fastrace::local::LocalSpan::add_event(event);
+    }
+}
@@ -0,0 +1,56 @@
+/// Describes how the source file paths recorded in `code.filepath` properties are rendered,
+/// configured with
+/// [`FastraceCompatLayer::with_path_style`](crate::FastraceCompatLayer::with_path_style).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathStyle {
+    /// Paths are recorded as the compiler reports them. Paths of workspace crates are relative to
+    /// the workspace, while paths of dependencies and of the standard library are absolute paths
+    /// on the build machine.
+    #[default]
+    Absolute,
+    /// Paths of dependencies are made relative to the directory they were downloaded to, e.g.,
+    /// `tokio-1.41.0/src/runtime/mod.rs`, and paths of the standard library relative to the
+    /// toolchain sources, e.g., `library/std/src/thread/mod.rs`. Other paths are recorded as is.
+    WorkspaceRelative,
+    /// Only the file name is recorded, e.g., `mod.rs`.
+    FileNameOnly,
+}
+
+/// Directories that absolute paths are made relative to by [`PathStyle::WorkspaceRelative`],
+/// along with the number of path components to skip after them, e.g., the registry index or the
+/// commit of the toolchain.
+#[cfg(not(feature = "strip-location"))]
+const SOURCE_ROOTS: [(&str, usize); 5] = [
+    ("/registry/src/", 1),
+    ("\\registry\\src\\", 1),
+    ("/git/checkouts/", 0),
+    ("\\git\\checkouts\\", 0),
+    ("/rustc/", 1),
+];
+
+/// Renders the source file path `path`, without the prefix `strip_prefix`, in the given style.
+#[cfg(not(feature = "strip-location"))]
+pub(crate) fn normalize_path<'a>(
+    path: &'a str,
+    strip_prefix: Option<&str>,
+    style: PathStyle,
+) -> &'a str {
+    let path = strip_prefix
+        .and_then(|prefix| path.strip_prefix(prefix))
+        .map_or(path, |path| path.trim_start_matches(['/', '\\']));
+    match style {
+        PathStyle::Absolute => path,
+        PathStyle::WorkspaceRelative => SOURCE_ROOTS
+            .iter()
+            .find_map(|(root, skip)| {
+                let start = path.find(root)? + root.len();
+                let mut rest = &path[start..];
+                for _ in 0..*skip {
+                    rest = &rest[rest.find(['/', '\\'])? + 1..];
+                }
+                Some(rest)
+            })
+            .unwrap_or(path),
+        PathStyle::FileNameOnly => path.rsplit(['/', '\\']).next().unwrap_or(path),
+    }
+}
@@ -0,0 +1,235 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use tracing_core::Level;
+use tracing_core::Metadata;
+use tracing_core::callsite;
+
+use crate::NoiseFilter;
+use crate::levels::TargetLevels;
+use crate::presets::target_matches;
+
+/// Decides how a tracing span is bridged by the
+/// [`FastraceCompatLayer`](crate::FastraceCompatLayer).
+///
+/// See [`FastraceCompatLayer::with_span_policy`](crate::FastraceCompatLayer::with_span_policy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanAction {
+    /// The span is recorded as a fastrace span.
+    Record,
+    /// The span is not recorded. Its children and events are attached to the nearest recorded
+    /// ancestor.
+    Discard,
+    /// The span is recorded as a single event, named after the span and carrying its fields, on
+    /// the nearest recorded ancestor. Its children and events are attached to that ancestor, and
+    /// fields recorded on it afterwards are recorded as another event.
+    EventOnly,
+    /// The span is aggregated with the other spans of the same name closed within the same
+    /// recorded parent. When the parent closes, a single representative child span is recorded
+    /// with the following properties:
+    /// - `repeat.count`: The number of aggregated spans
+    /// - `duration.min_ns`, `duration.max_ns`, `duration.p50_ns`: Statistics of their durations
+    ///
+    /// Fields, children, and events of aggregated spans are not kept on the representative span;
+    /// children and events are attached to the parent instead. This is meant for loops creating
+    /// many structurally identical spans.
+    Aggregate,
+}
+
+/// How the [`FastraceCompatLayer`](crate::FastraceCompatLayer) treats the spans or events of a
+/// callsite, as returned by
+/// [`FastraceCompatLayer::evaluate`](crate::FastraceCompatLayer::evaluate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Spans are recorded as fastrace spans, and events as fastrace events.
+    Record,
+    /// Spans are recorded as fastrace spans, unless their trace is not sampled by the
+    /// [`AdaptiveSampler`](crate::AdaptiveSampler).
+    Sample,
+    /// Spans are recorded as events, see [`SpanAction::EventOnly`], and events are folded into
+    /// properties or counters of their span, see
+    /// [`FastraceCompatLayer::with_event_folding`](crate::FastraceCompatLayer::with_event_folding).
+    Demote,
+    /// Spans are aggregated, see [`SpanAction::Aggregate`].
+    Aggregate,
+    /// Spans and events are not recorded.
+    Drop,
+}
+
+type DecideFn = dyn Fn(&Metadata<'_>) -> SpanAction + Send + Sync;
+
+/// A user-provided [`SpanAction`] decision.
+pub(crate) struct SpanPolicy {
+    decide: Box<DecideFn>,
+}
+
+impl SpanPolicy {
+    pub(crate) fn new(
+        decide: impl Fn(&Metadata<'_>) -> SpanAction + Send + Sync + 'static,
+    ) -> Self {
+        SpanPolicy {
+            decide: Box::new(decide),
+        }
+    }
+
+    pub(crate) fn decide(&self, meta: &Metadata<'_>) -> SpanAction {
+        (self.decide)(meta)
+    }
+}
+
+/// The filters deciding which spans and events are bridged, and how.
+#[derive(Default)]
+pub(crate) struct Filters {
+    reporter_targets: Vec<Cow<'static, str>>,
+    excluded_module_paths: Vec<Cow<'static, str>>,
+    target_levels: Option<TargetLevels>,
+    span_policy: Option<SpanPolicy>,
+    noise_filter: Option<NoiseFilter>,
+    span_level_threshold: Option<Level>,
+}
+
+impl Filters {
+    pub(crate) fn with_reporter_targets(self, targets: Vec<Cow<'static, str>>) -> Self {
+        Filters {
+            reporter_targets: targets,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_excluded_module_paths(self, paths: Vec<Cow<'static, str>>) -> Self {
+        Filters {
+            excluded_module_paths: paths,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_target_levels(self, levels: TargetLevels) -> Self {
+        Filters {
+            target_levels: Some(levels),
+            ..self
+        }
+    }
+
+    pub(crate) fn with_span_policy(self, policy: SpanPolicy) -> Self {
+        Filters {
+            span_policy: Some(policy),
+            ..self
+        }
+    }
+
+    /// Installs `filter`, merging its rules into the noise filter already installed, if any.
+    pub(crate) fn with_noise_filter(mut self, filter: NoiseFilter) -> Self {
+        match &mut self.noise_filter {
+            Some(existing) => existing.extend(filter),
+            None => self.noise_filter = Some(filter),
+        }
+        self
+    }
+
+    pub(crate) fn with_span_level_threshold(self, level: Level) -> Self {
+        Filters {
+            span_level_threshold: Some(level),
+            ..self
+        }
+    }
+
+    /// Decides how the spans of a callsite are bridged, combining the target and module
+    /// filters, the span policy, the noise filter, and the level threshold.
+    pub(crate) fn span_action(&self, meta: &Metadata<'_>) -> SpanAction {
+        if !self.is_enabled(meta) {
+            return SpanAction::Discard;
+        }
+        let mut action = self
+            .span_policy
+            .as_ref()
+            .map_or(SpanAction::Record, |policy| policy.decide(meta));
+        if action == SpanAction::Record {
+            if let Some(filtered) = self
+                .noise_filter
+                .as_ref()
+                .and_then(|filter| filter.span_action(meta))
+            {
+                action = filtered;
+            }
+        }
+        if action == SpanAction::Record
+            && self
+                .span_level_threshold
+                .is_some_and(|threshold| *meta.level() > threshold)
+        {
+            return SpanAction::EventOnly;
+        }
+        action
+    }
+
+    /// Returns `true` if an event with the given metadata should be bridged.
+    pub(crate) fn is_event_enabled(&self, meta: &Metadata<'_>) -> bool {
+        self.is_enabled(meta)
+            && self
+                .noise_filter
+                .as_ref()
+                .map_or(true, |filter| filter.is_event_enabled(meta))
+    }
+
+    /// Returns `true` unless the span or event with the given metadata was emitted by the
+    /// reporter, from an excluded module, or below the level of its target.
+    fn is_enabled(&self, meta: &Metadata<'_>) -> bool {
+        let reporter = self
+            .reporter_targets
+            .iter()
+            .any(|target| target_matches(meta.target(), target));
+        let excluded = meta.module_path().is_some_and(|module_path| {
+            self.excluded_module_paths
+                .iter()
+                .any(|path| target_matches(module_path, path.trim_end_matches("::")))
+        });
+        !reporter
+            && !excluded
+            && self
+                .target_levels
+                .as_ref()
+                .map_or(true, |levels| levels.is_enabled(meta))
+    }
+}
+
+/// The [`SpanAction`] of each span callsite, decided once when the callsite is registered.
+pub(crate) struct SpanActions {
+    actions: RwLock<HashMap<callsite::Identifier, SpanAction>>,
+}
+
+impl SpanActions {
+    pub(crate) fn new() -> Self {
+        SpanActions {
+            actions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Caches the action decided for a span callsite.
+    pub(crate) fn insert(&self, meta: &'static Metadata<'static>, action: SpanAction) {
+        self.actions
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(meta.callsite(), action);
+    }
+
+    /// Returns the action for a span, calling `decide` only if its callsite has not been
+    /// registered, e.g., because the layer was not installed when it was.
+    pub(crate) fn get(
+        &self,
+        meta: &'static Metadata<'static>,
+        decide: impl FnOnce() -> SpanAction,
+    ) -> SpanAction {
+        let registered = self
+            .actions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&meta.callsite())
+            .copied();
+        registered.unwrap_or_else(|| {
+            let action = decide();
+            self.insert(meta, action);
+            action
+        })
+    }
+}
@@ -0,0 +1,117 @@
+use std::borrow::Cow;
+
+use tracing_core::Level;
+use tracing_core::Metadata;
+
+use crate::SpanAction;
+
+/// Curated [`NoiseFilter`]s for well-known noisy libraries.
+///
+/// # Example
+///
+/// ```
+/// use fastrace_tracing::FastraceCompatLayer;
+/// use fastrace_tracing::Presets;
+///
+/// let layer = FastraceCompatLayer::<tracing_subscriber::Registry>::new()
+///     .with_noise_filter(Presets::quiet_http_stack())
+///     .with_noise_filter(Presets::quiet_tokio_runtime());
+/// ```
+pub struct Presets;
+
+impl Presets {
+    /// Drops the spans of the HTTP stack internals (`hyper`, `hyper_util`, `h2`, `tower`, and
+    /// `rustls`), and their events below `INFO`, or below `WARN` for `h2` and `rustls`.
+    ///
+    /// Spans of `tower_http`, which usually describe requests, are kept.
+    pub fn quiet_http_stack() -> NoiseFilter {
+        NoiseFilter::new()
+            .with_rule("hyper", SpanAction::Discard, Level::INFO)
+            .with_rule("hyper_util", SpanAction::Discard, Level::INFO)
+            .with_rule("h2", SpanAction::Discard, Level::WARN)
+            .with_rule("tower", SpanAction::Discard, Level::INFO)
+            .with_rule("rustls", SpanAction::Discard, Level::WARN)
+    }
+
+    /// Drops the spans the tokio runtime emits for tasks and resources when built with
+    /// `tokio_unstable`, and their events below `WARN`.
+    pub fn quiet_tokio_runtime() -> NoiseFilter {
+        NoiseFilter::new()
+            .with_rule("tokio", SpanAction::Discard, Level::WARN)
+            .with_rule("runtime", SpanAction::Discard, Level::WARN)
+    }
+}
+
+/// A set of rules demoting or dropping the spans and events of given targets.
+///
+/// Noise filters are installed with
+/// [`FastraceCompatLayer::with_noise_filter`](crate::FastraceCompatLayer::with_noise_filter). See
+/// [`Presets`] for curated filters.
+#[derive(Debug, Clone, Default)]
+pub struct NoiseFilter {
+    rules: Vec<NoiseRule>,
+}
+
+#[derive(Debug, Clone)]
+struct NoiseRule {
+    target: Cow<'static, str>,
+    span_action: SpanAction,
+    event_level: Level,
+}
+
+impl NoiseFilter {
+    /// Creates an empty [`NoiseFilter`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule for `target` and its submodules: their spans are bridged according to
+    /// `span_action`, and their events less severe than `event_level` are dropped.
+    ///
+    /// When several rules match, the one with the longest target wins.
+    pub fn with_rule(
+        mut self,
+        target: impl Into<Cow<'static, str>>,
+        span_action: SpanAction,
+        event_level: Level,
+    ) -> Self {
+        self.rules.push(NoiseRule {
+            target: target.into(),
+            span_action,
+            event_level,
+        });
+        self
+    }
+
+    /// Merges the rules of `other` into `self`.
+    pub(crate) fn extend(&mut self, other: NoiseFilter) {
+        self.rules.extend(other.rules);
+    }
+
+    /// Returns how a span with the given metadata is bridged, or `None` if no rule matches.
+    pub(crate) fn span_action(&self, meta: &Metadata<'_>) -> Option<SpanAction> {
+        self.rule(meta.target()).map(|rule| rule.span_action)
+    }
+
+    /// Returns `true` if an event with the given metadata should be recorded.
+    pub(crate) fn is_event_enabled(&self, meta: &Metadata<'_>) -> bool {
+        self.rule(meta.target())
+            .map_or(true, |rule| *meta.level() <= rule.event_level)
+    }
+
+    fn rule(&self, target: &str) -> Option<&NoiseRule> {
+        self.rules
+            .iter()
+            .filter(|rule| target_matches(target, &rule.target))
+            .max_by_key(|rule| rule.target.len())
+    }
+}
+
+/// Returns `true` if `target` is `prefix` or one of its submodules. The empty prefix matches
+/// every target.
+pub(crate) fn target_matches(target: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || target
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
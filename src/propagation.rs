@@ -0,0 +1,295 @@
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use fastrace::prelude::SpanContext;
+use fastrace::prelude::SpanId;
+use fastrace::prelude::TraceId;
+
+/// Encodes and decodes span contexts in the [B3] propagation format used by Zipkin.
+///
+/// Both the single `b3` header and the multiple `X-B3-*` headers are supported. 64-bit trace ids
+/// are extended to 128 bits with zeros. B3 values can also be passed in the `links` span field,
+/// see [`FastraceCompatLayer`](crate::FastraceCompatLayer).
+///
+/// [B3]: https://github.com/openzipkin/b3-propagation
+///
+/// # Example
+///
+/// ```
+/// use fastrace::prelude::*;
+/// use fastrace_tracing::B3;
+///
+/// let parent = B3::decode("80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1")
+///     .unwrap_or_else(SpanContext::random);
+/// let root = Span::root("request", parent);
+///
+/// let header = B3::encode(&SpanContext::from_span(&root).unwrap_or(parent));
+/// ```
+pub struct B3;
+
+impl B3 {
+    /// The name of the single B3 header.
+    pub const HEADER: &'static str = "b3";
+    /// The name of the B3 trace id header.
+    pub const TRACE_ID_HEADER: &'static str = "x-b3-traceid";
+    /// The name of the B3 span id header.
+    pub const SPAN_ID_HEADER: &'static str = "x-b3-spanid";
+    /// The name of the B3 sampling decision header.
+    pub const SAMPLED_HEADER: &'static str = "x-b3-sampled";
+    /// The name of the B3 debug flag header.
+    pub const FLAGS_HEADER: &'static str = "x-b3-flags";
+
+    /// Decodes the value of a single `b3` header, e.g.,
+    /// `80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1`.
+    ///
+    /// Returns `None` if the value is invalid or only carries a sampling decision.
+    pub fn decode(value: &str) -> Option<SpanContext> {
+        let mut parts = value.trim().split('-');
+        let trace_id = parse_trace_id(parts.next()?)?;
+        let span_id = parse_span_id(parts.next()?)?;
+        let sampled = match parts.next() {
+            None | Some("1") | Some("d") => true,
+            Some("0") => false,
+            Some(_) => return None,
+        };
+        Some(SpanContext::new(trace_id, span_id).sampled(sampled))
+    }
+
+    /// Encodes `span_context` as the value of a single `b3` header.
+    pub fn encode(span_context: &SpanContext) -> String {
+        format!(
+            "{:032x}-{:016x}-{}",
+            span_context.trace_id.0,
+            span_context.span_id.0,
+            if span_context.sampled { "1" } else { "0" }
+        )
+    }
+
+    /// Decodes the values of the `X-B3-TraceId`, `X-B3-SpanId`, `X-B3-Sampled`, and `X-B3-Flags`
+    /// headers.
+    ///
+    /// Returns `None` if the trace id or span id is missing or invalid.
+    pub fn decode_multi(
+        trace_id: &str,
+        span_id: &str,
+        sampled: Option<&str>,
+        flags: Option<&str>,
+    ) -> Option<SpanContext> {
+        let trace_id = parse_trace_id(trace_id.trim())?;
+        let span_id = parse_span_id(span_id.trim())?;
+        let debug = flags.is_some_and(|flags| flags.trim() == "1");
+        let sampled = debug || !matches!(sampled.map(str::trim), Some("0" | "false"));
+        Some(SpanContext::new(trace_id, span_id).sampled(sampled))
+    }
+
+    /// Encodes `span_context` as the `X-B3-TraceId`, `X-B3-SpanId`, and `X-B3-Sampled` headers.
+    pub fn encode_multi(span_context: &SpanContext) -> [(&'static str, String); 3] {
+        [
+            (
+                Self::TRACE_ID_HEADER,
+                format!("{:032x}", span_context.trace_id.0),
+            ),
+            (
+                Self::SPAN_ID_HEADER,
+                format!("{:016x}", span_context.span_id.0),
+            ),
+            (
+                Self::SAMPLED_HEADER,
+                if span_context.sampled { "1" } else { "0" }.to_string(),
+            ),
+        ]
+    }
+}
+
+/// Encodes and decodes span contexts in the `X-Amzn-Trace-Id` header format of AWS X-Ray, e.g.,
+/// `Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1`.
+///
+/// X-Ray trace ids embed the Unix epoch, in seconds, at which the trace started in their upper
+/// 32 bits, and X-Ray rejects trace ids whose epoch is too far in the past. Use
+/// [`XRay::new_trace_id`] to start traces that are propagated to X-Ray. X-Ray values can also be
+/// passed in the `links` span field, see [`FastraceCompatLayer`](crate::FastraceCompatLayer).
+///
+/// # Example
+///
+/// ```
+/// use fastrace::prelude::*;
+/// use fastrace_tracing::XRay;
+///
+/// let parent = XRay::decode("Root=1-5759e988-bd862e3fe1be46a994272793;Sampled=1")
+///     .unwrap_or_else(|| SpanContext::new(XRay::new_trace_id(), SpanId(0)));
+/// let root = Span::root("request", parent);
+/// ```
+pub struct XRay;
+
+impl XRay {
+    /// The name of the X-Ray trace header.
+    pub const HEADER: &'static str = "x-amzn-trace-id";
+
+    /// Decodes the value of an `X-Amzn-Trace-Id` header.
+    ///
+    /// Load balancers only set the `Root` of the trace, in which case the returned span context
+    /// has a zero span id, making spans attached to it the roots of the trace. Returns `None` if
+    /// the value does not carry a valid `Root`.
+    pub fn decode(value: &str) -> Option<SpanContext> {
+        let mut trace_id = None;
+        let mut span_id = SpanId(0);
+        let mut sampled = true;
+        for field in value.split(';') {
+            let Some((key, value)) = field.split_once('=') else {
+                continue;
+            };
+            match key.trim() {
+                "Root" => trace_id = Some(parse_xray_trace_id(value.trim())?),
+                "Parent" => span_id = parse_span_id(value.trim())?,
+                "Sampled" => sampled = value.trim() != "0",
+                _ => {}
+            }
+        }
+        Some(SpanContext::new(trace_id?, span_id).sampled(sampled))
+    }
+
+    /// Encodes `span_context` as the value of an `X-Amzn-Trace-Id` header.
+    pub fn encode(span_context: &SpanContext) -> String {
+        let trace_id = span_context.trace_id.0;
+        format!(
+            "Root=1-{:08x}-{:024x};Parent={:016x};Sampled={}",
+            trace_id >> 96,
+            trace_id & ((1 << 96) - 1),
+            span_context.span_id.0,
+            if span_context.sampled { "1" } else { "0" }
+        )
+    }
+
+    /// Returns a random trace id embedding the current time, as X-Ray expects.
+    pub fn new_trace_id() -> TraceId {
+        let epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as u32;
+        let random = SpanContext::random().trace_id.0 & ((1 << 96) - 1);
+        TraceId(((epoch as u128) << 96) | random)
+    }
+}
+
+/// Encodes and decodes span contexts in the `x-datadog-*` headers of the Datadog tracers.
+///
+/// Datadog ids are decimal 64-bit integers. The upper 64 bits of 128-bit trace ids are carried
+/// as hexadecimal in the `_dd.p.tid` tag of the `x-datadog-tags` header, and are zero if the
+/// tag is missing, e.g., when the upstream tracer only generates 64-bit trace ids.
+///
+/// # Example
+///
+/// ```
+/// use fastrace::prelude::*;
+/// use fastrace_tracing::Datadog;
+///
+/// let parent = Datadog::decode("1234567890", "987654321", Some("1"), None)
+///     .unwrap_or_else(SpanContext::random);
+/// let root = Span::root("request", parent);
+///
+/// for (header, value) in Datadog::encode(&SpanContext::from_span(&root).unwrap_or(parent)) {
+///     // Set `header` to `value` on the outgoing request.
+/// }
+/// ```
+pub struct Datadog;
+
+impl Datadog {
+    /// The name of the Datadog trace id header.
+    pub const TRACE_ID_HEADER: &'static str = "x-datadog-trace-id";
+    /// The name of the Datadog parent span id header.
+    pub const PARENT_ID_HEADER: &'static str = "x-datadog-parent-id";
+    /// The name of the Datadog sampling priority header.
+    pub const SAMPLING_PRIORITY_HEADER: &'static str = "x-datadog-sampling-priority";
+    /// The name of the Datadog propagated tags header.
+    pub const TAGS_HEADER: &'static str = "x-datadog-tags";
+
+    /// The propagated tag holding the upper 64 bits of 128-bit trace ids.
+    const TRACE_ID_HIGH_TAG: &'static str = "_dd.p.tid";
+
+    /// Decodes the values of the `x-datadog-trace-id`, `x-datadog-parent-id`,
+    /// `x-datadog-sampling-priority`, and `x-datadog-tags` headers.
+    ///
+    /// A sampling priority of zero or less marks the trace as not sampled. Returns `None` if the
+    /// trace id or parent id is missing or invalid.
+    pub fn decode(
+        trace_id: &str,
+        parent_id: &str,
+        sampling_priority: Option<&str>,
+        tags: Option<&str>,
+    ) -> Option<SpanContext> {
+        let low = trace_id.trim().parse::<u64>().ok()?;
+        let parent_id = parent_id.trim().parse::<u64>().ok().filter(|id| *id != 0)?;
+        let high = tags
+            .into_iter()
+            .flat_map(|tags| tags.split(','))
+            .filter_map(|tag| tag.split_once('='))
+            .find(|(key, _)| key.trim() == Self::TRACE_ID_HIGH_TAG)
+            .and_then(|(_, value)| parse_hex(value.trim(), 16))
+            .unwrap_or(0);
+        let trace_id = (high << 64) | low as u128;
+        if trace_id == 0 {
+            return None;
+        }
+        let sampled = sampling_priority
+            .and_then(|priority| priority.trim().parse::<i32>().ok())
+            .map_or(true, |priority| priority > 0);
+        Some(SpanContext::new(TraceId(trace_id), SpanId(parent_id)).sampled(sampled))
+    }
+
+    /// Encodes `span_context` as `x-datadog-*` headers.
+    ///
+    /// The `x-datadog-tags` header is only included for trace ids wider than 64 bits.
+    pub fn encode(span_context: &SpanContext) -> Vec<(&'static str, String)> {
+        let trace_id = span_context.trace_id.0;
+        let mut headers = vec![
+            (Self::TRACE_ID_HEADER, (trace_id as u64).to_string()),
+            (Self::PARENT_ID_HEADER, span_context.span_id.0.to_string()),
+            (
+                Self::SAMPLING_PRIORITY_HEADER,
+                if span_context.sampled { "1" } else { "0" }.to_string(),
+            ),
+        ];
+        let high = (trace_id >> 64) as u64;
+        if high != 0 {
+            headers.push((
+                Self::TAGS_HEADER,
+                format!("{}={:016x}", Self::TRACE_ID_HIGH_TAG, high),
+            ));
+        }
+        headers
+    }
+}
+
+/// Parses an X-Ray trace id, e.g., `1-5759e988-bd862e3fe1be46a994272793`.
+fn parse_xray_trace_id(value: &str) -> Option<TraceId> {
+    let mut parts = value.split('-');
+    if parts.next()? != "1" {
+        return None;
+    }
+    let epoch = parse_hex(parts.next()?, 8)?;
+    let random = parse_hex(parts.next()?, 24)?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(TraceId((epoch << 96) | random))
+}
+
+/// Parses a non-zero hexadecimal id of `len` digits.
+fn parse_hex(value: &str, len: usize) -> Option<u128> {
+    if value.len() != len || !value.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u128::from_str_radix(value, 16).ok().filter(|id| *id != 0)
+}
+
+/// Parses a 128-bit or 64-bit hexadecimal trace id.
+fn parse_trace_id(value: &str) -> Option<TraceId> {
+    parse_hex(value, 32)
+        .or_else(|| parse_hex(value, 16))
+        .map(TraceId)
+}
+
+/// Parses a 64-bit hexadecimal span id.
+fn parse_span_id(value: &str) -> Option<SpanId> {
+    parse_hex(value, 16).map(|id| SpanId(id as u64))
+}
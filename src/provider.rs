@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+
+use tracing_core::Metadata;
+
+use crate::PathStyle;
+use crate::Property;
+
+/// Contributes properties to every span bridged by the
+/// [`FastraceCompatLayer`](crate::FastraceCompatLayer).
+///
+/// Providers are registered with
+/// [`FastraceCompatLayer::with_property_provider`](crate::FastraceCompatLayer::with_property_provider)
+/// and are called when a span is created, on the thread creating it. This decouples enrichment,
+/// e.g., with a request id from a task-local, feature flags, or build info, from the
+/// instrumented call sites.
+///
+/// The trait is implemented for closures with the same signature as
+/// [`PropertyProvider::provide`].
+///
+/// # Example
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// use fastrace_tracing::PropertyProvider;
+/// use tracing::Metadata;
+///
+/// struct BuildInfo;
+///
+/// impl PropertyProvider for BuildInfo {
+///     fn provide(
+///         &self,
+///         _meta: &Metadata<'_>,
+///         properties: &mut Vec<(Cow<'static, str>, Cow<'static, str>)>,
+///     ) {
+///         properties.push(("build.version".into(), env!("CARGO_PKG_VERSION").into()));
+///     }
+/// }
+/// ```
+pub trait PropertyProvider: Send + Sync + 'static {
+    /// Pushes the properties to record on a new span with the given metadata.
+    fn provide(
+        &self,
+        meta: &Metadata<'_>,
+        properties: &mut Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    );
+}
+
+impl<F> PropertyProvider for F
+where F: Fn(&Metadata<'_>, &mut Vec<(Cow<'static, str>, Cow<'static, str>)>) + Send + Sync + 'static
+{
+    fn provide(
+        &self,
+        meta: &Metadata<'_>,
+        properties: &mut Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    ) {
+        self(meta, properties)
+    }
+}
+
+/// The properties recorded on every bridged span, according to the configuration.
+pub(crate) struct SpanProperties {
+    #[cfg_attr(feature = "strip-location", allow(dead_code))]
+    location: bool,
+    #[cfg_attr(feature = "strip-location", allow(dead_code))]
+    path_style: PathStyle,
+    #[cfg_attr(feature = "strip-location", allow(dead_code))]
+    path_prefix: Option<Cow<'static, str>>,
+    threads: bool,
+    #[cfg(feature = "tokio")]
+    task_info: bool,
+    level: bool,
+    providers: Vec<Box<dyn PropertyProvider>>,
+}
+
+impl Default for SpanProperties {
+    fn default() -> Self {
+        SpanProperties {
+            location: true,
+            path_style: PathStyle::Absolute,
+            path_prefix: None,
+            threads: true,
+            #[cfg(feature = "tokio")]
+            task_info: false,
+            level: false,
+            providers: Vec::new(),
+        }
+    }
+}
+
+impl SpanProperties {
+    pub(crate) fn with_location(self, location: bool) -> Self {
+        SpanProperties { location, ..self }
+    }
+
+    pub(crate) fn with_path_style(self, path_style: PathStyle) -> Self {
+        SpanProperties { path_style, ..self }
+    }
+
+    pub(crate) fn with_path_prefix(self, prefix: Cow<'static, str>) -> Self {
+        SpanProperties {
+            path_prefix: Some(prefix),
+            ..self
+        }
+    }
+
+    pub(crate) fn with_threads(self, threads: bool) -> Self {
+        SpanProperties { threads, ..self }
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) fn with_task_info(self, task_info: bool) -> Self {
+        SpanProperties { task_info, ..self }
+    }
+
+    pub(crate) fn with_level(self, level: bool) -> Self {
+        SpanProperties { level, ..self }
+    }
+
+    pub(crate) fn with_provider(mut self, provider: Box<dyn PropertyProvider>) -> Self {
+        self.providers.push(provider);
+        self
+    }
+
+    /// Returns `true` if the source code location of spans and events is recorded.
+    #[cfg(not(feature = "strip-location"))]
+    pub(crate) fn records_location(&self) -> bool {
+        self.location
+    }
+
+    /// Renders the source file path `file` as configured for `code.filepath` properties.
+    #[cfg(not(feature = "strip-location"))]
+    pub(crate) fn code_filepath<'a>(&self, file: &'a str) -> &'a str {
+        crate::path::normalize_path(file, self.path_prefix.as_deref(), self.path_style)
+    }
+
+    /// Returns the properties of a new span with the given metadata.
+    pub(crate) fn properties(&self, meta: &Metadata<'_>) -> Vec<Property> {
+        let mut props: Vec<Property> = Vec::with_capacity(8);
+        #[cfg(not(feature = "strip-location"))]
+        if self.location {
+            if let Some(filename) = meta.file() {
+                let filename = self.code_filepath(filename);
+                props.push(("code.filepath".into(), filename.to_string().into()));
+            }
+
+            if let Some(module) = meta.module_path() {
+                props.push(("code.namespace".into(), module.to_string().into()));
+            }
+
+            if let Some(line) = meta.line() {
+                props.push(("code.lineno".into(), line.to_string().into()));
+            }
+        }
+
+        if self.threads {
+            crate::THREAD_ID.with(|id| {
+                props.push(("thread.id".into(), id.to_string().into()));
+            });
+            if let Some(name) = std::thread::current().name() {
+                props.push(("thread.name".into(), name.to_string().into()));
+            }
+        }
+
+        #[cfg(feature = "tokio")]
+        if self.task_info {
+            crate::task_local::task_properties(&mut props);
+        }
+
+        if self.level {
+            props.push(("level".into(), meta.level().to_string().into()));
+        }
+
+        for provider in &self.providers {
+            provider.provide(meta, &mut props);
+        }
+
+        props
+    }
+}
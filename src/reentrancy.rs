@@ -0,0 +1,161 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use fastrace::collector::Reporter;
+use fastrace::collector::SpanRecord;
+use pin_project_lite::pin_project;
+
+thread_local! {
+    static SUPPRESSED: Cell<bool> = const { Cell::new(false) };
+    static HOLDING_EXTENSIONS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Returns `true` if the spans and events of the current thread must not be bridged, because
+/// the thread is running a layer callback or a [`GuardedReporter`].
+pub(crate) fn is_suppressed() -> bool {
+    SUPPRESSED.try_with(Cell::get).unwrap_or(true)
+}
+
+/// Suppresses the bridging of spans and events on the current thread until the returned guard
+/// is dropped.
+pub(crate) fn suppress() -> SuppressGuard {
+    let previous = SUPPRESSED
+        .try_with(|suppressed| suppressed.replace(true))
+        .unwrap_or(true);
+    SuppressGuard { previous }
+}
+
+pub(crate) struct SuppressGuard {
+    previous: bool,
+}
+
+impl Drop for SuppressGuard {
+    fn drop(&mut self) {
+        let _ = SUPPRESSED.try_with(|suppressed| suppressed.set(self.previous));
+    }
+}
+
+/// Returns `true` if the current thread may be holding the registry extensions of a span,
+/// because it is running a layer callback or a closure given access to them.
+///
+/// The extensions are behind a lock that is not reentrant, so they must not be accessed again,
+/// e.g., from a panic hook.
+pub(crate) fn may_hold_extensions() -> bool {
+    HOLDING_EXTENSIONS.try_with(Cell::get).unwrap_or(true)
+}
+
+/// Marks the current thread as possibly holding the registry extensions of a span until the
+/// returned guard is dropped.
+pub(crate) fn hold_extensions() -> HoldGuard {
+    let previous = HOLDING_EXTENSIONS
+        .try_with(|holding| holding.replace(true))
+        .unwrap_or(true);
+    HoldGuard { previous }
+}
+
+pub(crate) struct HoldGuard {
+    previous: bool,
+}
+
+impl Drop for HoldGuard {
+    fn drop(&mut self) {
+        let _ = HOLDING_EXTENSIONS.try_with(|holding| holding.set(self.previous));
+    }
+}
+
+/// Runs `f` without bridging the spans and events it emits on the current thread.
+///
+/// This is meant for known-noisy sections, e.g., cache warm-up loops or calls into third-party
+/// code, that should not be traced. Spans created within `f` are not bridged, and neither are
+/// their events, even if they are entered later.
+///
+/// # Example
+///
+/// ```
+/// let warmed = fastrace_tracing::suppressed(|| {
+///     for key in 0..1000 {
+///         tracing::debug!(key, "warming cache");
+///     }
+///     1000
+/// });
+/// ```
+pub fn suppressed<R>(f: impl FnOnce() -> R) -> R {
+    let _guard = suppress();
+    f()
+}
+
+/// Wraps `future` so that the spans and events it emits while being polled are not bridged.
+///
+/// This is the async counterpart of [`suppressed`]. Tasks spawned by `future` are not covered.
+///
+/// # Example
+///
+/// ```
+/// async fn warm_up_cache() {
+///     tracing::debug!("warming cache");
+/// }
+///
+/// let future = fastrace_tracing::suppressed_async(warm_up_cache());
+/// ```
+pub fn suppressed_async<F: Future>(future: F) -> Suppressed<F> {
+    Suppressed { inner: future }
+}
+
+pin_project! {
+    /// A future whose spans and events are not bridged.
+    ///
+    /// This is created by [`suppressed_async`].
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub struct Suppressed<F> {
+        #[pin]
+        inner: F,
+    }
+}
+
+impl<F: Future> Future for Suppressed<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let _guard = suppress();
+        self.project().inner.poll(cx)
+    }
+}
+
+/// A [`Reporter`] that prevents the spans and events it emits through `tracing` from being
+/// bridged back to fastrace.
+///
+/// Reporters, or the HTTP clients they use, may be instrumented with `tracing`. Without this
+/// guard, their spans would be bridged and reported again, creating a feedback loop. Spans and
+/// events emitted on other threads, e.g., by the tasks of an async HTTP client, are not covered,
+/// so their targets should also be configured with
+/// [`FastraceCompatLayer::with_reporter_targets`](crate::FastraceCompatLayer::with_reporter_targets).
+///
+/// # Example
+///
+/// ```
+/// use fastrace::collector::Config;
+/// use fastrace::collector::ConsoleReporter;
+/// use fastrace_tracing::GuardedReporter;
+///
+/// fastrace::set_reporter(GuardedReporter::new(ConsoleReporter), Config::default());
+/// ```
+pub struct GuardedReporter<R> {
+    inner: R,
+}
+
+impl<R> GuardedReporter<R> {
+    /// Creates a [`GuardedReporter`] reporting to `inner`.
+    pub fn new(inner: R) -> Self {
+        GuardedReporter { inner }
+    }
+}
+
+impl<R: Reporter> Reporter for GuardedReporter<R> {
+    fn report(&mut self, spans: Vec<SpanRecord>) {
+        let _guard = suppress();
+        self.inner.report(spans);
+    }
+}
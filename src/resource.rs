@@ -0,0 +1,130 @@
+use std::sync::LazyLock;
+
+use tracing_core::Metadata;
+
+use crate::Property;
+use crate::PropertyProvider;
+
+/// The properties recorded on the root spans created by the layer only.
+#[derive(Default)]
+pub(crate) struct RootProperties {
+    process_info: bool,
+    service_version: Option<String>,
+    #[cfg(feature = "k8s")]
+    k8s_info: bool,
+    properties: Vec<Property>,
+    providers: Vec<Box<dyn PropertyProvider>>,
+}
+
+impl RootProperties {
+    pub(crate) fn with_process_info(self, process_info: bool) -> Self {
+        RootProperties {
+            process_info,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_service_version(self, version: String) -> Self {
+        RootProperties {
+            service_version: Some(version),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "k8s")]
+    pub(crate) fn with_k8s_info(self, k8s_info: bool) -> Self {
+        RootProperties { k8s_info, ..self }
+    }
+
+    pub(crate) fn with_properties(
+        mut self,
+        properties: impl IntoIterator<Item = Property>,
+    ) -> Self {
+        self.properties.extend(properties);
+        self
+    }
+
+    pub(crate) fn with_provider(mut self, provider: Box<dyn PropertyProvider>) -> Self {
+        self.providers.push(provider);
+        self
+    }
+
+    /// Adds the root properties to `root`, a new root span with the given metadata.
+    pub(crate) fn apply(&self, mut root: fastrace::Span, meta: &Metadata<'_>) -> fastrace::Span {
+        if self.process_info {
+            let properties = process_properties(self.service_version.as_deref());
+            root = root.with_properties(|| properties);
+        }
+        #[cfg(feature = "k8s")]
+        if self.k8s_info {
+            root = root.with_properties(k8s_properties);
+        }
+        if !self.properties.is_empty() || !self.providers.is_empty() {
+            let mut properties = self.properties.clone();
+            for provider in &self.providers {
+                provider.provide(meta, &mut properties);
+            }
+            root = root.with_properties(|| properties);
+        }
+        root
+    }
+}
+
+/// Returns the `process.pid`, `host.name`, and `service.version` properties describing the
+/// current process.
+///
+/// `service_version` takes precedence over the `SERVICE_VERSION` environment variable.
+fn process_properties(service_version: Option<&str>) -> Vec<Property> {
+    static PROCESS: LazyLock<Vec<Property>> = LazyLock::new(|| {
+        let mut props: Vec<Property> = Vec::with_capacity(2);
+        props.push(("process.pid".into(), std::process::id().to_string().into()));
+        if let Some(host) = host_name() {
+            props.push(("host.name".into(), host.into()));
+        }
+        props
+    });
+    static SERVICE_VERSION: LazyLock<Option<String>> =
+        LazyLock::new(|| std::env::var("SERVICE_VERSION").ok());
+
+    let mut props = PROCESS.clone();
+    if let Some(version) = service_version.or(SERVICE_VERSION.as_deref()) {
+        props.push(("service.version".into(), version.to_string().into()));
+    }
+    props
+}
+
+/// Returns the `k8s.pod.name`, `k8s.namespace.name`, and `k8s.node.name` properties, read from
+/// environment variables set through the Kubernetes downward API.
+///
+/// Each property is read from the first of its variables that is set, e.g., `K8S_POD_NAME` or
+/// `POD_NAME`. Properties whose variables are not set are omitted.
+#[cfg(feature = "k8s")]
+fn k8s_properties() -> Vec<Property> {
+    const K8S_ENV: [(&str, &str, &str); 3] = [
+        ("k8s.pod.name", "K8S_POD_NAME", "POD_NAME"),
+        ("k8s.namespace.name", "K8S_NAMESPACE_NAME", "POD_NAMESPACE"),
+        ("k8s.node.name", "K8S_NODE_NAME", "NODE_NAME"),
+    ];
+    static K8S: LazyLock<Vec<Property>> = LazyLock::new(|| {
+        K8S_ENV
+            .iter()
+            .filter_map(|(key, var, fallback)| {
+                let value = std::env::var(var)
+                    .or_else(|_| std::env::var(fallback))
+                    .ok()?;
+                Some(((*key).into(), value.into()))
+            })
+            .collect()
+    });
+
+    K8S.clone()
+}
+
+fn host_name() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+}
@@ -0,0 +1,154 @@
+use std::borrow::Cow;
+
+use crate::FieldPolicy;
+use crate::Property;
+use crate::hashing::FieldHasher;
+
+/// Rewrites property values and event names before they are forwarded to fastrace.
+#[derive(Default)]
+pub(crate) struct Sanitizer {
+    strip_ansi: bool,
+    control_characters: Option<ControlCharacters>,
+    field_policy: Option<FieldPolicy>,
+    field_hasher: Option<FieldHasher>,
+}
+
+impl Sanitizer {
+    pub(crate) fn with_ansi_stripping(self, strip_ansi: bool) -> Self {
+        Sanitizer { strip_ansi, ..self }
+    }
+
+    pub(crate) fn with_control_characters(self, control_characters: ControlCharacters) -> Self {
+        Sanitizer {
+            control_characters: Some(control_characters),
+            ..self
+        }
+    }
+
+    pub(crate) fn with_field_policy(self, policy: FieldPolicy) -> Self {
+        Sanitizer {
+            field_policy: Some(policy),
+            ..self
+        }
+    }
+
+    pub(crate) fn with_field_hasher(self, hasher: FieldHasher) -> Self {
+        Sanitizer {
+            field_hasher: Some(hasher),
+            ..self
+        }
+    }
+
+    /// Rewrites a property value or event name.
+    pub(crate) fn sanitize(&self, value: &mut Cow<'static, str>) {
+        if self.strip_ansi {
+            strip_ansi(value);
+        }
+        if let Some(policy) = self.control_characters {
+            sanitize_control_characters(value, policy);
+        }
+    }
+
+    /// Applies the field policy to `properties`, then rewrites their values, and finally hashes
+    /// the values of the hashed fields.
+    pub(crate) fn apply(&self, properties: &mut Vec<Property>) {
+        if let Some(policy) = &self.field_policy {
+            policy.apply(properties);
+        }
+        for (_, value) in properties.iter_mut() {
+            self.sanitize(value);
+        }
+        if let Some(hasher) = &self.field_hasher {
+            hasher.apply(properties);
+        }
+    }
+}
+
+/// Removes the ANSI escape sequences, e.g., color codes, from `value`.
+fn strip_ansi(value: &mut Cow<'static, str>) {
+    if !value.contains('\x1b') {
+        return;
+    }
+
+    let mut stripped = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+        match chars.next() {
+            // Control sequences end with a byte in the `@` to `~` range.
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // Operating system commands end with BEL or `ESC \`.
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' {
+                        chars.next_if_eq(&'\\');
+                        break;
+                    }
+                }
+            }
+            // Other escape sequences are two characters long.
+            _ => {}
+        }
+    }
+    *value = Cow::Owned(stripped);
+}
+
+/// Describes how control characters in property values and event names are rewritten,
+/// configured with
+/// [`FastraceCompatLayer::with_control_characters`](crate::FastraceCompatLayer::with_control_characters).
+///
+/// Control characters include the C0 and C1 control codes, e.g., newlines and NUL bytes, the
+/// bidirectional formatting characters, and the byte order mark, which some backends reject or
+/// mangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCharacters {
+    /// Control characters are escaped like in Rust string literals, e.g., `\n` or `\u{202e}`.
+    Escape,
+    /// Control characters are replaced with the replacement character `U+FFFD`.
+    Replace,
+}
+
+/// Rewrites the control characters in `value` according to `policy`.
+fn sanitize_control_characters(value: &mut Cow<'static, str>, policy: ControlCharacters) {
+    if !value.chars().any(is_control_character) {
+        return;
+    }
+
+    let mut sanitized = String::with_capacity(value.len());
+    for c in value.chars() {
+        if !is_control_character(c) {
+            sanitized.push(c);
+            continue;
+        }
+        match policy {
+            ControlCharacters::Escape => sanitized.extend(c.escape_default()),
+            ControlCharacters::Replace => sanitized.push(char::REPLACEMENT_CHARACTER),
+        }
+    }
+    *value = Cow::Owned(sanitized);
+}
+
+fn is_control_character(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{061c}'
+                | '\u{200e}'
+                | '\u{200f}'
+                | '\u{202a}'..='\u{202e}'
+                | '\u{2066}'..='\u{2069}'
+                | '\u{feff}'
+        )
+}
@@ -0,0 +1,74 @@
+use std::borrow::Cow;
+use std::time::Duration;
+use std::time::Instant;
+
+use fastrace::prelude::SpanContext;
+
+use crate::Property;
+
+/// The segments of a long-lived bridged span, stored in its registry extensions, for
+/// [`FastraceCompatLayer::with_span_segmentation`](crate::FastraceCompatLayer::with_span_segmentation).
+pub(crate) struct SpanSegments {
+    name: Cow<'static, str>,
+    parent: SpanContext,
+    properties: Vec<Property>,
+    index: usize,
+    events: usize,
+    start: Instant,
+}
+
+impl SpanSegments {
+    /// Creates the segments of a span named `name`, whose segments are children of `parent` and
+    /// carry `properties`.
+    pub(crate) fn new(
+        name: Cow<'static, str>,
+        parent: SpanContext,
+        properties: Vec<Property>,
+    ) -> Self {
+        SpanSegments {
+            name,
+            parent,
+            properties,
+            index: 1,
+            events: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// Counts an event recorded on the current segment.
+    ///
+    /// Returns `true` if the current segment has reached `max_events` events or `max_duration`,
+    /// in which case a new segment should be started.
+    pub(crate) fn record_event(&mut self, max_events: usize, max_duration: Duration) -> bool {
+        self.events += 1;
+        self.events >= max_events || self.start.elapsed() >= max_duration
+    }
+
+    /// Returns the segment following `current`, which the caller ends by dropping it.
+    ///
+    /// Segments are numbered by a `segment.index` property, and each segment records the span id
+    /// of the previous one in a `segment.previous_span_id` property.
+    pub(crate) fn next_segment(&mut self, current: &fastrace::Span) -> fastrace::Span {
+        let previous_index = self.index;
+        current.add_property(|| ("segment.index", previous_index.to_string()));
+
+        self.index += 1;
+        self.events = 0;
+        self.start = Instant::now();
+
+        let index = self.index;
+        let properties = self.properties.clone();
+        let mut next = fastrace::Span::root(self.name.clone(), self.parent)
+            .with_properties(|| properties)
+            .with_property(|| ("segment.index", index.to_string()));
+        if let Some(previous) = SpanContext::from_span(current) {
+            next = next.with_property(|| {
+                (
+                    "segment.previous_span_id",
+                    format!("{:016x}", previous.span_id.0),
+                )
+            });
+        }
+        next
+    }
+}
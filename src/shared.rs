@@ -0,0 +1,110 @@
+use std::any::TypeId;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use tracing_core::Event;
+use tracing_core::Metadata;
+use tracing_core::Subscriber;
+use tracing_core::span::Attributes;
+use tracing_core::span::Id;
+use tracing_core::span::Record;
+use tracing_core::subscriber::Interest;
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::FastraceCompatLayer;
+
+/// A [`FastraceCompatLayer`] that can be installed in a subscriber while a handle to it is kept.
+///
+/// A layer is moved into the subscriber it is installed in, so it can no longer be queried
+/// afterwards. A [`SharedLayer`] is a cheaply cloneable handle to the layer: one clone is
+/// installed, and the others dereference to the same [`FastraceCompatLayer`]. This gives access
+/// to the methods taking `&self` at runtime: [`stats`](FastraceCompatLayer::stats),
+/// [`evaluate`](FastraceCompatLayer::evaluate), and
+/// [`set_shadow_mode`](FastraceCompatLayer::set_shadow_mode). The rest of the configuration is
+/// fixed once the layer is built.
+///
+/// # Example
+///
+/// ```
+/// use fastrace_tracing::Decision;
+/// use fastrace_tracing::FastraceCompatLayer;
+/// use fastrace_tracing::SharedLayer;
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let layer = SharedLayer::new(FastraceCompatLayer::new());
+/// let subscriber = tracing_subscriber::Registry::default().with(layer.clone());
+/// tracing::subscriber::set_global_default(subscriber).unwrap();
+///
+/// let span = tracing::info_span!("request");
+/// let meta = span.metadata().unwrap();
+/// assert_eq!(layer.evaluate(meta), Decision::Record);
+/// assert_eq!(layer.stats().spans, 1);
+///
+/// layer.set_shadow_mode(true);
+/// ```
+pub struct SharedLayer<S> {
+    inner: Arc<FastraceCompatLayer<S>>,
+}
+
+impl<S> SharedLayer<S> {
+    /// Creates a [`SharedLayer`] sharing `layer`.
+    pub fn new(layer: FastraceCompatLayer<S>) -> Self {
+        SharedLayer {
+            inner: Arc::new(layer),
+        }
+    }
+}
+
+impl<S> Clone for SharedLayer<S> {
+    fn clone(&self) -> Self {
+        SharedLayer {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S> Deref for SharedLayer<S> {
+    type Target = FastraceCompatLayer<S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<S> Layer<S> for SharedLayer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn register_callsite(&self, meta: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(meta)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_new_span(attrs, id, ctx)
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        self.inner.on_record(id, values, ctx)
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx)
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        self.inner.on_event(event, ctx)
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            return Some(self as *const _ as *const ());
+        }
+        self.inner.downcast_raw(id)
+    }
+}
@@ -0,0 +1,101 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+/// A handle stopping the layers it is installed in from bridging new tracing spans and events,
+/// installed with
+/// [`FastraceCompatLayer::with_shutdown`](crate::FastraceCompatLayer::with_shutdown).
+///
+/// It is a cheap handle, so a clone can be kept to trigger the shutdown. Requires the `shutdown`
+/// feature.
+///
+/// # Example
+///
+/// ```
+/// use fastrace_tracing::FastraceCompatLayer;
+/// use fastrace_tracing::ShutdownHandle;
+///
+/// let shutdown = ShutdownHandle::new();
+/// let layer =
+///     FastraceCompatLayer::<tracing_subscriber::Registry>::new().with_shutdown(shutdown.clone());
+///
+/// shutdown.shutdown();
+/// assert!(shutdown.is_shut_down());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownHandle {
+    shut_down: Arc<AtomicBool>,
+}
+
+impl ShutdownHandle {
+    /// Creates a [`ShutdownHandle`] that has not been triggered.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` once [`shutdown`](Self::shutdown) has been called.
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down.load(Ordering::Relaxed)
+    }
+
+    /// Stops bridging new tracing spans and events, and flushes fastrace.
+    ///
+    /// Spans that are already open are still reported when they close. Call this once the
+    /// application is shutting down, so that the final spans are not lost when the process
+    /// exits.
+    pub fn shutdown(&self) {
+        self.shut_down.store(true, Ordering::Relaxed);
+        fastrace::flush();
+    }
+
+    /// Waits for `signal` to complete, then calls [`shutdown`](Self::shutdown).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn example(shutdown_requested: impl std::future::Future<Output = ()>) {
+    /// let shutdown = fastrace_tracing::ShutdownHandle::new();
+    /// shutdown.shutdown_on(shutdown_requested).await;
+    /// # }
+    /// ```
+    pub async fn shutdown_on(&self, signal: impl Future<Output = ()>) {
+        signal.await;
+        self.shutdown();
+    }
+
+    /// Waits for `SIGINT` or, on Unix, `SIGTERM`, then calls [`shutdown`](Self::shutdown).
+    ///
+    /// This is typically spawned at startup, e.g.,
+    /// `tokio::spawn(async move { shutdown.shutdown_on_signal().await })`. Requires the `signal`
+    /// feature and a tokio runtime with IO enabled.
+    ///
+    /// Listening for a signal replaces its default action of terminating the process, for the
+    /// rest of its lifetime. The process does not exit on its own once this returns: the caller
+    /// must exit, e.g., with [`std::process::exit`], once the rest of the application has shut
+    /// down.
+    #[cfg(feature = "signal")]
+    pub async fn shutdown_on_signal(&self) {
+        #[cfg(unix)]
+        let terminate = async {
+            use tokio::signal::unix::SignalKind;
+
+            match tokio::signal::unix::signal(SignalKind::terminate()) {
+                Ok(mut terminate) => {
+                    terminate.recv().await;
+                }
+                Err(_) => std::future::pending().await,
+            }
+        };
+        #[cfg(not(unix))]
+        let terminate = std::future::pending::<()>();
+
+        self.shutdown_on(async {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate => {}
+            }
+        })
+        .await;
+    }
+}
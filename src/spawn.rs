@@ -0,0 +1,84 @@
+use std::cell::Cell;
+use std::future::Future;
+#[cfg(not(feature = "strip-location"))]
+use std::panic::Location;
+
+use fastrace::prelude::SpanContext;
+use tokio::task::JoinHandle;
+
+use crate::Property;
+
+tokio::task_local! {
+    /// The origin of a task spawned with [`spawn`], until the first span of the task takes it.
+    static SPAWN_ORIGIN: Cell<Option<SpawnOrigin>>;
+}
+
+/// Where a task was spawned from.
+struct SpawnOrigin {
+    parent: Option<SpanContext>,
+    #[cfg(not(feature = "strip-location"))]
+    location: &'static Location<'static>,
+}
+
+/// Spawns a tokio task, recording where it was spawned from on the first span created within
+/// it.
+///
+/// Tasks spawned with `tokio::spawn` do not inherit the tracing span or the fastrace local
+/// parent of their spawner, so their spans often start new traces. This records the following
+/// properties on the first bridged span created within the task, tying it back to its origin:
+/// - `spawned_from.trace_id`, `spawned_from.span_id`: The fastrace span bridged from the current
+///   tracing span, or the fastrace local parent, when the task was spawned, if any
+/// - `spawn.location`: The source location of the call to [`spawn`], unless the `strip-location`
+///   feature is enabled
+///
+/// Requires the `tokio` feature.
+///
+/// # Example
+///
+/// ```
+/// let runtime = tokio::runtime::Builder::new_current_thread()
+///     .build()
+///     .unwrap();
+/// runtime.block_on(async {
+///     let handle = fastrace_tracing::spawn(async {
+///         let _span = tracing::info_span!("background job").entered();
+///     });
+///     handle.await.unwrap();
+/// });
+/// ```
+#[cfg_attr(not(feature = "strip-location"), track_caller)]
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let parent = crate::with_current_fastrace_span(|span| SpanContext::from_span(span))
+        .flatten()
+        .or_else(SpanContext::current_local_parent);
+    let origin = SpawnOrigin {
+        parent,
+        #[cfg(not(feature = "strip-location"))]
+        location: Location::caller(),
+    };
+    tokio::spawn(SPAWN_ORIGIN.scope(Cell::new(Some(origin)), future))
+}
+
+/// Records the origin of the current task, if it was spawned with [`spawn`] and no span has
+/// been created within it yet.
+pub(crate) fn spawn_origin_properties(properties: &mut Vec<Property>) {
+    let Ok(Some(origin)) = SPAWN_ORIGIN.try_with(Cell::take) else {
+        return;
+    };
+    if let Some(parent) = origin.parent {
+        properties.push((
+            "spawned_from.trace_id".into(),
+            format!("{:032x}", parent.trace_id.0).into(),
+        ));
+        properties.push((
+            "spawned_from.span_id".into(),
+            format!("{:016x}", parent.span_id.0).into(),
+        ));
+    }
+    #[cfg(not(feature = "strip-location"))]
+    properties.push(("spawn.location".into(), origin.location.to_string().into()));
+}
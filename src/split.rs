@@ -0,0 +1,197 @@
+use std::borrow::Cow;
+use std::hash::Hasher;
+use std::time::Duration;
+
+use fastrace::prelude::SpanContext;
+use fastrace::prelude::SpanId;
+use fastrace::prelude::TraceId;
+use siphasher::sip128::Hasher128;
+use siphasher::sip128::SipHasher24;
+use tracing_core::Metadata;
+
+use crate::Property;
+use crate::RootNaming;
+use crate::presets::target_matches;
+
+/// Decides where new traces start, and how their roots are named and identified.
+#[derive(Default)]
+pub(crate) struct Roots {
+    splits: Vec<RootSplit>,
+    trace_id_fields: Option<Vec<String>>,
+    naming: RootNaming,
+    thread_roots: Option<Duration>,
+    attach_explicit_roots: bool,
+}
+
+impl Roots {
+    pub(crate) fn with_splits(self, splits: Vec<RootSplit>) -> Self {
+        Roots { splits, ..self }
+    }
+
+    pub(crate) fn with_trace_id_fields(self, fields: Vec<String>) -> Self {
+        Roots {
+            trace_id_fields: Some(fields),
+            ..self
+        }
+    }
+
+    pub(crate) fn with_naming(self, naming: RootNaming) -> Self {
+        Roots { naming, ..self }
+    }
+
+    pub(crate) fn with_thread_roots(self, max_age: Duration) -> Self {
+        Roots {
+            thread_roots: Some(max_age),
+            ..self
+        }
+    }
+
+    pub(crate) fn with_explicit_roots_attached(self, attach: bool) -> Self {
+        Roots {
+            attach_explicit_roots: attach,
+            ..self
+        }
+    }
+
+    /// Returns `true` if the fields of spans are needed when they are created, to split their
+    /// trace or derive their trace id.
+    pub(crate) fn needs_fields(&self) -> bool {
+        self.trace_id_fields.is_some() || self.splits.iter().any(RootSplit::has_field)
+    }
+
+    /// Returns `true` if a span with the given metadata and properties starts a new trace.
+    pub(crate) fn splits(&self, meta: &Metadata<'_>, properties: &[Property]) -> bool {
+        self.splits
+            .iter()
+            .any(|rule| rule.matches(meta, properties))
+    }
+
+    /// Returns the maximum age of the root of each thread, if spans without any parent are
+    /// attached to it.
+    pub(crate) fn thread_roots(&self) -> Option<Duration> {
+        self.thread_roots
+    }
+
+    /// Returns `true` if spans created with an explicit `parent: None` are attached to the
+    /// fastrace local parent.
+    pub(crate) fn attaches_explicit_roots(&self) -> bool {
+        self.attach_explicit_roots
+    }
+
+    /// Returns the name of the root created for the span `name`, which has no parent at all.
+    pub(crate) fn implicit_root_name(
+        &self,
+        name: Cow<'static, str>,
+        meta: &Metadata<'_>,
+    ) -> Cow<'static, str> {
+        self.naming.name(name, meta)
+    }
+
+    /// Returns the parent context of a root span with the given metadata and properties.
+    pub(crate) fn context(&self, meta: &Metadata<'_>, properties: &[Property]) -> SpanContext {
+        let Some(fields) = &self.trace_id_fields else {
+            return SpanContext::random();
+        };
+
+        // SipHash with a fixed key is stable across processes and builds.
+        let mut hasher = SipHasher24::new();
+        let mut write = |value: &str| {
+            // Terminate each value so that `("ab", "c")` and `("a", "bc")` hash differently.
+            hasher.write(value.as_bytes());
+            hasher.write_u8(0xff);
+        };
+        write(meta.target());
+        write(meta.name());
+        for field in fields {
+            let value = properties
+                .iter()
+                .find(|(key, _)| key == field)
+                .map(|(_, value)| value.as_ref())
+                .unwrap_or_default();
+            write(field);
+            write(value);
+        }
+        SpanContext::new(TraceId(hasher.finish128().as_u128()), SpanId(0))
+    }
+}
+
+/// A rule selecting spans that start a new trace, linked to the trace they were created in,
+/// configured with
+/// [`FastraceCompatLayer::with_root_splits`](crate::FastraceCompatLayer::with_root_splits).
+///
+/// A span matches if it matches every condition of the rule. A rule without conditions matches
+/// every span.
+///
+/// # Example
+///
+/// ```
+/// use fastrace_tracing::RootSplit;
+///
+/// let rule = RootSplit::new()
+///     .with_name("http.request")
+///     .with_field("span.kind", "server");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RootSplit {
+    name: Option<Cow<'static, str>>,
+    target: Option<Cow<'static, str>>,
+    field: Option<(Cow<'static, str>, Cow<'static, str>)>,
+}
+
+impl RootSplit {
+    /// Creates a [`RootSplit`] without conditions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires spans to be named `name`.
+    pub fn with_name(self, name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Requires spans to have `target`, or one of its submodules, as target.
+    pub fn with_target(self, target: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            target: Some(target.into()),
+            ..self
+        }
+    }
+
+    /// Requires spans to be created with the field `key` recorded as `value`.
+    ///
+    /// `key` refers to the property key of the field, e.g., as converted by
+    /// [`FastraceCompatLayer::with_key_case`](crate::FastraceCompatLayer::with_key_case).
+    pub fn with_field(
+        self,
+        key: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Self {
+            field: Some((key.into(), value.into())),
+            ..self
+        }
+    }
+
+    /// Returns `true` if a span with the given metadata and properties matches the rule.
+    pub(crate) fn matches(&self, meta: &Metadata<'_>, properties: &[Property]) -> bool {
+        self.name.as_ref().map_or(true, |name| meta.name() == name)
+            && self
+                .target
+                .as_ref()
+                .map_or(true, |target| target_matches(meta.target(), target))
+            && self.field.as_ref().map_or(true, |field| {
+                properties
+                    .iter()
+                    .any(|(key, value)| key == &field.0 && value == &field.1)
+            })
+    }
+
+    /// Returns `true` if the rule needs the fields of spans to be converted when they are
+    /// created.
+    pub(crate) fn has_field(&self) -> bool {
+        self.field.is_some()
+    }
+}
@@ -0,0 +1,59 @@
+use std::fmt::Write;
+
+/// Describes how the chain of causes of an error is rendered in the `exception.stacktrace` and
+/// `<field>.chain` properties, configured with
+/// [`FastraceCompatLayer::with_stacktrace_format`](crate::FastraceCompatLayer::with_stacktrace_format).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StacktraceFormat {
+    /// The causes are rendered as a Rust list, e.g., `["timed out", "connection reset"]`.
+    #[default]
+    Debug,
+    /// The causes are rendered one per line.
+    Lines,
+    /// The causes are rendered as a JSON array of strings.
+    Json,
+    /// The causes are rendered on a single line, separated by `: `, e.g.,
+    /// `timed out: connection reset`.
+    Compact,
+}
+
+impl StacktraceFormat {
+    /// Renders `causes` in this format.
+    pub(crate) fn render(self, causes: &[String]) -> String {
+        match self {
+            StacktraceFormat::Debug => format!("{:?}", causes),
+            StacktraceFormat::Lines => causes.join("\n"),
+            StacktraceFormat::Json => {
+                let mut json = String::from("[");
+                for (i, cause) in causes.iter().enumerate() {
+                    if i > 0 {
+                        json.push(',');
+                    }
+                    push_json_string(&mut json, cause);
+                }
+                json.push(']');
+                json
+            }
+            StacktraceFormat::Compact => causes.join(": "),
+        }
+    }
+}
+
+/// Appends `value` to `json` as a JSON string literal.
+fn push_json_string(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
@@ -0,0 +1,39 @@
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+/// A snapshot of the activity of a [`FastraceCompatLayer`](crate::FastraceCompatLayer), returned
+/// by [`FastraceCompatLayer::stats`](crate::FastraceCompatLayer::stats).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Stats {
+    /// The number of spans bridged since the layer was created.
+    pub spans: u64,
+    /// The number of events bridged since the layer was created.
+    pub events: u64,
+    /// The current ratio of the [`AdaptiveSampler`](crate::AdaptiveSampler), if one is installed.
+    pub sampling_ratio: Option<f64>,
+}
+
+/// Counts the spans and events bridged by a layer.
+#[derive(Default)]
+pub(crate) struct Counters {
+    spans: AtomicU64,
+    events: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn count_span(&self) {
+        self.spans.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_event(&self) {
+        self.events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn spans(&self) -> u64 {
+        self.spans.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn events(&self) -> u64 {
+        self.events.load(Ordering::Relaxed)
+    }
+}
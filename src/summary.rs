@@ -0,0 +1,50 @@
+use std::borrow::Cow;
+
+use crate::FIELD_EXCEPTION_MESSAGE;
+use crate::Property;
+
+/// The state of a bridged span needed to emit its summary event when it closes, for
+/// [`FastraceCompatLayer::with_close_summary`](crate::FastraceCompatLayer::with_close_summary).
+#[derive(Default)]
+pub(crate) struct CloseSummary {
+    properties: Vec<Property>,
+    failed: bool,
+}
+
+impl CloseSummary {
+    /// Keeps the properties among `properties` whose key is one of `keys`, replacing the values
+    /// kept so far, and marks the span as failed if an error was recorded.
+    pub(crate) fn capture(&mut self, keys: &[Cow<'static, str>], properties: &[Property]) {
+        for (key, value) in properties {
+            if key == FIELD_EXCEPTION_MESSAGE || (key == "status.code" && value == "ERROR") {
+                self.failed = true;
+            }
+            if !keys.contains(key) {
+                continue;
+            }
+            match self.properties.iter_mut().find(|(k, _)| k == key) {
+                Some((_, kept)) => *kept = value.clone(),
+                None => self.properties.push((key.clone(), value.clone())),
+            }
+        }
+    }
+
+    /// Marks the span as failed, e.g., because an `ERROR` event was recorded within it.
+    pub(crate) fn fail(&mut self) {
+        self.failed = true;
+    }
+
+    /// Adds the summary event of the span named `name` to `fastrace_span`.
+    pub(crate) fn emit(self, name: &str, fastrace_span: &fastrace::Span) {
+        let mut properties: Vec<Property> = Vec::with_capacity(self.properties.len() + 3);
+        properties.push(("span.name".into(), name.to_string().into()));
+        if let Some(duration) = fastrace_span.elapsed() {
+            properties.push(("duration_ns".into(), duration.as_nanos().to_string().into()));
+        }
+        let status = if self.failed { "ERROR" } else { "OK" };
+        properties.push(("status.code".into(), status.into()));
+        properties.extend(self.properties);
+        let summary = fastrace::Event::new("span.summary").with_properties(|| properties);
+        fastrace_span.add_event(summary);
+    }
+}
@@ -0,0 +1,47 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use tokio::task::LocalKey;
+use tracing_core::Metadata;
+
+use crate::PropertyProvider;
+
+/// Records the value of a tokio task-local as a property on the spans created within a task
+/// where it is set.
+pub(crate) struct TaskLocalProvider<T: 'static> {
+    name: Cow<'static, str>,
+    key: &'static LocalKey<T>,
+}
+
+impl<T: 'static> TaskLocalProvider<T> {
+    pub(crate) fn new(name: Cow<'static, str>, key: &'static LocalKey<T>) -> Self {
+        TaskLocalProvider { name, key }
+    }
+}
+
+impl<T> PropertyProvider for TaskLocalProvider<T>
+where T: fmt::Display + 'static
+{
+    fn provide(
+        &self,
+        _meta: &Metadata<'_>,
+        properties: &mut Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    ) {
+        // The task-local is not set outside of its scope.
+        let _ = self
+            .key
+            .try_with(|value| properties.push((self.name.clone(), value.to_string().into())));
+    }
+}
+
+/// Records the id of the current tokio task, and the name of the runtime worker thread it runs
+/// on, if called within a spawned task.
+pub(crate) fn task_properties(properties: &mut Vec<(Cow<'static, str>, Cow<'static, str>)>) {
+    let Some(id) = tokio::task::try_id() else {
+        return;
+    };
+    properties.push(("task.id".into(), id.to_string().into()));
+    if let Some(name) = std::thread::current().name() {
+        properties.push(("task.runtime".into(), name.to_string().into()));
+    }
+}
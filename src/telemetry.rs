@@ -0,0 +1,130 @@
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use fastrace::prelude::SpanContext;
+
+/// The number of calls and the time spent in a layer callback since the last report.
+#[derive(Default)]
+struct CallbackStats {
+    count: AtomicU64,
+    time_ns: AtomicU64,
+}
+
+impl CallbackStats {
+    fn take(&self) -> (u64, u64) {
+        (
+            self.count.swap(0, Ordering::Relaxed),
+            self.time_ns.swap(0, Ordering::Relaxed),
+        )
+    }
+}
+
+/// The layer callbacks measured by [`SelfTelemetry`].
+#[derive(Clone, Copy)]
+pub(crate) enum Callback {
+    NewSpan,
+    Event,
+}
+
+/// Measures the time the layer spends bridging spans and events, and periodically reports it.
+pub(crate) struct SelfTelemetry {
+    interval: Duration,
+    last_report: Mutex<Instant>,
+    new_span: CallbackStats,
+    event: CallbackStats,
+    /// The number of spans and events bridged since the last report.
+    spans: AtomicU64,
+    events: AtomicU64,
+}
+
+impl SelfTelemetry {
+    pub(crate) fn new(interval: Duration) -> Self {
+        SelfTelemetry {
+            interval,
+            last_report: Mutex::new(Instant::now()),
+            new_span: CallbackStats::default(),
+            event: CallbackStats::default(),
+            spans: AtomicU64::new(0),
+            events: AtomicU64::new(0),
+        }
+    }
+
+    /// Counts a bridged span.
+    pub(crate) fn count_span(&self) {
+        self.spans.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a bridged event.
+    pub(crate) fn count_event(&self) {
+        self.events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Starts measuring a call to `callback`, which ends when the returned guard is dropped.
+    pub(crate) fn measure(&self, callback: Callback) -> Measurement<'_> {
+        Measurement {
+            telemetry: self,
+            callback,
+            start: Instant::now(),
+        }
+    }
+
+    fn stats(&self, callback: Callback) -> &CallbackStats {
+        match callback {
+            Callback::NewSpan => &self.new_span,
+            Callback::Event => &self.event,
+        }
+    }
+
+    /// Reports the measurements taken since the last report, at most once per interval, as a
+    /// `fastrace_tracing.overhead` root span.
+    fn report(&self) {
+        let elapsed = {
+            let Ok(mut last_report) = self.last_report.try_lock() else {
+                return;
+            };
+            let elapsed = last_report.elapsed();
+            if elapsed < self.interval {
+                return;
+            }
+            *last_report = Instant::now();
+            elapsed
+        };
+
+        let (new_span_count, new_span_time_ns) = self.new_span.take();
+        let (event_count, event_time_ns) = self.event.take();
+        let spans = self.spans.swap(0, Ordering::Relaxed);
+        let events = self.events.swap(0, Ordering::Relaxed);
+        let _report = fastrace::Span::root("fastrace_tracing.overhead", SpanContext::random())
+            .with_properties(|| {
+                [
+                    ("overhead.interval_ns", elapsed.as_nanos().to_string()),
+                    ("overhead.on_new_span.count", new_span_count.to_string()),
+                    ("overhead.on_new_span.time_ns", new_span_time_ns.to_string()),
+                    ("overhead.on_event.count", event_count.to_string()),
+                    ("overhead.on_event.time_ns", event_time_ns.to_string()),
+                    ("overhead.spans", spans.to_string()),
+                    ("overhead.events", events.to_string()),
+                ]
+            });
+    }
+}
+
+/// A guard measuring a layer callback until it is dropped.
+pub(crate) struct Measurement<'a> {
+    telemetry: &'a SelfTelemetry,
+    callback: Callback,
+    start: Instant,
+}
+
+impl Drop for Measurement<'_> {
+    fn drop(&mut self) {
+        let time_ns = self.start.elapsed().as_nanos() as u64;
+        let stats = self.telemetry.stats(self.callback);
+        stats.count.fetch_add(1, Ordering::Relaxed);
+        stats.time_ns.fetch_add(time_ns, Ordering::Relaxed);
+        self.telemetry.report();
+    }
+}
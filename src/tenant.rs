@@ -0,0 +1,80 @@
+use std::borrow::Cow;
+
+use crate::Property;
+
+/// The property key of the tenant of bridged spans.
+const FIELD_TENANT_ID: &str = "tenant.id";
+
+/// Resolves the tenant that bridged spans belong to, configured with
+/// [`FastraceCompatLayer::with_tenant_resolver`](crate::FastraceCompatLayer::with_tenant_resolver).
+///
+/// # Example
+///
+/// ```
+/// use std::cell::RefCell;
+///
+/// use fastrace_tracing::TenantResolver;
+///
+/// thread_local! {
+///     static TENANT: RefCell<Option<String>> = const { RefCell::new(None) };
+/// }
+///
+/// let from_field = TenantResolver::Field("customer_id".into());
+/// let from_thread_local =
+///     TenantResolver::Lookup(|| TENANT.with(|tenant| tenant.borrow().clone()));
+/// ```
+#[derive(Debug, Clone)]
+pub enum TenantResolver {
+    /// The tenant is the value of the given field of the span.
+    ///
+    /// The field refers to the property key of the field, e.g., as converted by
+    /// [`FastraceCompatLayer::with_key_case`](crate::FastraceCompatLayer::with_key_case).
+    Field(Cow<'static, str>),
+    /// The tenant is looked up by the given function when the span is created, e.g., from a
+    /// task-local or thread-local variable.
+    Lookup(fn() -> Option<String>),
+}
+
+impl TenantResolver {
+    /// Resolves the tenant of a span with the given properties, nested in a span of the tenant
+    /// `inherited`, if any.
+    ///
+    /// Spans carrying the field of the resolver belong to its value, and other spans to the
+    /// tenant they inherit, if any.
+    pub(crate) fn resolve(
+        &self,
+        properties: &[Property],
+        inherited: Option<Tenant>,
+    ) -> Option<Tenant> {
+        match self {
+            TenantResolver::Field(field) => properties
+                .iter()
+                .find(|(key, _)| key == field)
+                .map(|(_, value)| Tenant(value.clone()))
+                .or(inherited),
+            TenantResolver::Lookup(lookup) => {
+                inherited.or_else(|| lookup().map(|tenant| Tenant(tenant.into())))
+            }
+        }
+    }
+
+    /// Returns `true` if the resolver needs the fields of spans to be converted when they are
+    /// created.
+    pub(crate) fn has_field(&self) -> bool {
+        matches!(self, TenantResolver::Field(_))
+    }
+}
+
+/// The tenant of a bridged span, stored in its registry extensions so that descendants inherit
+/// it.
+#[derive(Clone)]
+pub(crate) struct Tenant(Cow<'static, str>);
+
+impl Tenant {
+    /// Records the tenant on a span with the given properties, unless they already carry it.
+    pub(crate) fn push_property(&self, properties: &mut Vec<Property>) {
+        if !properties.iter().any(|(key, _)| key == FIELD_TENANT_ID) {
+            properties.push((FIELD_TENANT_ID.into(), self.0.clone()));
+        }
+    }
+}
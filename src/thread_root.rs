@@ -0,0 +1,55 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+thread_local! {
+    /// The root of the current thread created by
+    /// [`FastraceCompatLayer::with_thread_roots`](crate::FastraceCompatLayer::with_thread_roots).
+    static THREAD_ROOT: RefCell<Option<ThreadRoot>> = const { RefCell::new(None) };
+}
+
+struct ThreadRoot {
+    span: fastrace::Span,
+    started: Instant,
+}
+
+/// Creates a span named `name` as a child of the root of the current thread.
+///
+/// The root is created with `new_root` if the thread has none yet, or if its root is older than
+/// `max_age`, in which case the previous root ends and is reported along with its children.
+///
+/// Returns `None` if the root of the thread is not available, e.g., while the thread exits.
+pub(crate) fn enter_with_thread_root(
+    name: Cow<'static, str>,
+    max_age: Duration,
+    new_root: impl FnOnce(Cow<'static, str>) -> fastrace::Span,
+) -> Option<fastrace::Span> {
+    THREAD_ROOT
+        .try_with(|root| {
+            // Creating a root may record tracing spans, e.g., in a property provider.
+            let mut root = root.try_borrow_mut().ok()?;
+            if root
+                .as_ref()
+                .map_or(true, |root| root.started.elapsed() >= max_age)
+            {
+                *root = Some(ThreadRoot {
+                    span: new_root(thread_root_name()),
+                    started: Instant::now(),
+                });
+            }
+            let root = root.as_ref()?;
+            Some(fastrace::Span::enter_with_parent(name, &root.span))
+        })
+        .ok()
+        .flatten()
+}
+
+/// Returns the name of the current thread, or its ID if it has no name.
+fn thread_root_name() -> Cow<'static, str> {
+    match thread::current().name() {
+        Some(name) => Cow::Owned(name.to_string()),
+        None => crate::THREAD_ID.with(|id| Cow::Owned(format!("thread-{}", **id))),
+    }
+}
@@ -0,0 +1,18 @@
+use tracing_core::Metadata;
+
+use crate::Property;
+
+/// Describes how values recorded on a tracing span after its bridged fastrace span has ended,
+/// e.g., because it timed out, are handled, configured with
+/// [`FastraceCompatLayer::with_late_records`](crate::FastraceCompatLayer::with_late_records).
+#[derive(Debug, Clone, Copy)]
+pub enum LateRecords {
+    /// The values are dropped.
+    Drop,
+    /// The values are recorded as an event, named after the span, on its nearest recorded
+    /// ancestor.
+    Event,
+    /// The values are passed to the callback as `(key, value)` properties, along with the
+    /// metadata of the span, e.g., to count them in a metric.
+    Callback(fn(&Metadata<'_>, &[Property])),
+}
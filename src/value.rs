@@ -0,0 +1,81 @@
+use std::fmt::Write;
+
+use crate::KeyCase;
+use crate::StacktraceFormat;
+
+/// A field value recorded by tracing, before it is converted to a property value.
+///
+/// Values are kept typed until they are recorded on fastrace spans and events, which only accept
+/// string values, and are then converted by
+/// [`FastraceCompatLayer::with_value_encoder`](crate::FastraceCompatLayer::with_value_encoder),
+/// or [`FieldValue::encode`] by default. Values recorded with `Debug` are formatted right away,
+/// since tracing only lends them for the duration of the callback, and are passed as
+/// [`FieldValue::Str`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldValue<'a> {
+    /// A value recorded with `record_bool`.
+    Bool(bool),
+    /// A value recorded with `record_i64`.
+    I64(i64),
+    /// A value recorded with `record_u64`.
+    U64(u64),
+    /// A value recorded with `record_f64`.
+    F64(f64),
+    /// A value recorded with `record_str`, or formatted with `Debug`.
+    Str(&'a str),
+    /// A value recorded with `record_bytes`.
+    Bytes(&'a [u8]),
+}
+
+impl FieldValue<'_> {
+    /// Converts the value to a property value, as tracing formats it.
+    ///
+    /// Bytes are formatted as space-separated hex digits, e.g., `[0a ff]`.
+    pub fn encode(&self) -> String {
+        match self {
+            FieldValue::Bool(value) => value.to_string(),
+            FieldValue::I64(value) => value.to_string(),
+            FieldValue::U64(value) => value.to_string(),
+            FieldValue::F64(value) => value.to_string(),
+            FieldValue::Str(value) => value.to_string(),
+            FieldValue::Bytes(value) => {
+                let mut encoded = String::with_capacity(value.len() * 3 + 1);
+                encoded.push('[');
+                for (i, byte) in value.iter().enumerate() {
+                    if i > 0 {
+                        encoded.push(' ');
+                    }
+                    let _ = write!(encoded, "{:02x}", byte);
+                }
+                encoded.push(']');
+                encoded
+            }
+        }
+    }
+}
+
+/// How field values are converted to properties, according to the configuration.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct FieldOptions {
+    pub(crate) key_case: Option<KeyCase>,
+    pub(crate) max_list_elements: Option<usize>,
+    pub(crate) max_error_causes: Option<usize>,
+    pub(crate) stacktrace_format: StacktraceFormat,
+    pub(crate) value_encoder: Option<fn(&FieldValue<'_>) -> String>,
+}
+
+impl FieldOptions {
+    /// Returns the property key of the field `name`.
+    pub(crate) fn key(self, name: &'static str) -> &'static str {
+        self.key_case
+            .map_or(name, |key_case| key_case.convert(name))
+    }
+
+    /// Converts a field value to a property value.
+    pub(crate) fn encode(self, value: FieldValue<'_>) -> String {
+        match self.value_encoder {
+            Some(encoder) => encoder(&value),
+            None => value.encode(),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::Once;
+use std::time::Duration;
+use std::time::Instant;
+
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace::prelude::*;
+use fastrace_tracing::AdaptiveSampler;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Installs a fastrace reporter, without which fastrace spans are not recorded.
+fn set_reporter() {
+    static REPORTER: Once = Once::new();
+    REPORTER.call_once(|| {
+        let (reporter, _spans) = TestReporter::new();
+        fastrace::set_reporter(reporter, Config::default());
+    });
+}
+
+/// Creates root spans at roughly `per_second` for `duration`.
+fn create_spans(per_second: u32, duration: Duration) {
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        let _span = tracing::info_span!("request").entered();
+        std::thread::sleep(Duration::from_secs(1) / per_second);
+    }
+}
+
+#[test]
+fn ratio_follows_throughput() {
+    set_reporter();
+    let sampler = AdaptiveSampler::new(50).with_min_ratio(0.01);
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_adaptive_sampling(sampler.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        create_spans(1000, Duration::from_millis(1200));
+        let ratio = sampler.ratio();
+        assert!((0.01..0.5).contains(&ratio), "ratio {ratio} under load");
+
+        let start = Instant::now();
+        while sampler.ratio() < 1.0 && start.elapsed() < Duration::from_secs(5) {
+            create_spans(10, Duration::from_millis(100));
+        }
+        assert_eq!(sampler.ratio(), 1.0);
+    });
+}
+
+#[test]
+fn ratio_is_clamped_to_min() {
+    set_reporter();
+    let sampler = AdaptiveSampler::new(0).with_min_ratio(0.25);
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_adaptive_sampling(sampler));
+    let dispatch = tracing::Dispatch::new(subscriber);
+
+    tracing::dispatcher::with_default(&dispatch, || {
+        create_spans(1000, Duration::from_millis(1200));
+    });
+    let layer = dispatch
+        .downcast_ref::<FastraceCompatLayer<tracing_subscriber::Registry>>()
+        .unwrap();
+    assert_eq!(layer.stats().sampling_ratio, Some(0.25));
+}
+
+#[test]
+fn spans_under_application_parent_are_not_measured() {
+    set_reporter();
+    let sampler = AdaptiveSampler::new(50);
+    let layer = FastraceCompatLayer::new().with_adaptive_sampling(sampler.clone());
+    let subscriber = tracing_subscriber::Registry::default().with(layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = Span::root("application", SpanContext::random());
+        let _guard = root.set_local_parent();
+        create_spans(1000, Duration::from_millis(1200));
+    });
+    assert_eq!(sampler.ratio(), 1.0);
+}
@@ -0,0 +1,33 @@
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn baggage_is_recorded_on_descendant_spans() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default().with(FastraceCompatLayer::new());
+
+    tracing::subscriber::with_default(subscriber, || {
+        let request = tracing::info_span!("request", baggage.tenant_id = "acme");
+        fastrace_tracing::set_baggage(&request, "user_id", "42");
+        let _request = request.entered();
+        let _query = tracing::info_span!("query").entered();
+        let _fetch = tracing::info_span!("fetch").entered();
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    for name in ["query", "fetch"] {
+        let span = spans.iter().find(|span| span.name == name).unwrap();
+        let property = |key: &str| {
+            span.properties
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.as_ref())
+        };
+        assert_eq!(property("tenant_id"), Some("acme"), "{name}");
+        assert_eq!(property("user_id"), Some("42"), "{name}");
+    }
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn batched_events_reach_their_span() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_event_batching(4, Duration::from_secs(3600)));
+    let dispatch = tracing::Dispatch::new(subscriber);
+
+    tracing::dispatcher::with_default(&dispatch, || {
+        let request = tracing::info_span!("request");
+        std::thread::scope(|scope| {
+            for thread in 0..2 {
+                let request = &request;
+                let dispatch = &dispatch;
+                scope.spawn(move || {
+                    tracing::dispatcher::with_default(dispatch, || {
+                        for i in 0..5 {
+                            tracing::info!(parent: request, thread, i, "worker event");
+                        }
+                    })
+                });
+            }
+        });
+        request.in_scope(|| {
+            for i in 0..10 {
+                tracing::info!(i, "request event");
+            }
+        });
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let request = spans.iter().find(|span| span.name == "request").unwrap();
+    let count = |name: &str| {
+        request
+            .events
+            .iter()
+            .filter(|event| event.name == name)
+            .count()
+    };
+    assert_eq!(count("request event"), 10);
+    assert_eq!(count("worker event"), 10);
+    assert!(request.events.iter().all(|event| {
+        event
+            .properties
+            .iter()
+            .any(|(key, _)| key == "event.time_unix_ns")
+    }));
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn stale_batches_are_added_by_other_threads() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_event_batching(100, Duration::from_millis(50)));
+    let dispatch = tracing::Dispatch::new(subscriber);
+
+    tracing::dispatcher::with_default(&dispatch, || {
+        let job = tracing::info_span!("job");
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                tracing::dispatcher::with_default(&dispatch, || {
+                    tracing::info!(parent: &job, "idle worker event");
+                })
+            });
+        });
+        std::thread::sleep(Duration::from_millis(100));
+        tracing::info_span!("poll").in_scope(|| tracing::info!("poll event"));
+        std::thread::sleep(Duration::from_millis(500));
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let job = spans.iter().find(|span| span.name == "job").unwrap();
+    let event = job
+        .events
+        .iter()
+        .find(|event| event.name == "idle worker event")
+        .unwrap();
+    // The batch of the idle thread is added by the next event, long before the span closes.
+    let end = job.begin_time_unix_ns + job.duration_ns;
+    assert!(event.timestamp_unix_ns + 250_000_000 < end);
+}
@@ -0,0 +1,38 @@
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn buffered_spans_are_replayed_by_set_reporter() {
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_early_buffer(3));
+
+    tracing::subscriber::with_default(subscriber, || {
+        {
+            let _startup = tracing::info_span!("startup").entered();
+            let _config = tracing::info_span!("config").entered();
+        }
+        let server = tracing::info_span!("server");
+        // The buffer holds `startup`, `config` and the open `server`.
+        drop(tracing::info_span!("discarded"));
+
+        let (reporter, spans) = TestReporter::new();
+        fastrace_tracing::set_reporter(reporter, Config::default());
+        fastrace::flush();
+        {
+            let spans = spans.lock();
+            let startup = spans.iter().find(|span| span.name == "startup").unwrap();
+            let config = spans.iter().find(|span| span.name == "config").unwrap();
+            assert_eq!(config.trace_id, startup.trace_id);
+            assert_eq!(config.parent_id, startup.span_id);
+            assert!(spans.iter().all(|span| span.name != "server"));
+        }
+
+        drop(server);
+        fastrace::flush();
+        let spans = spans.lock();
+        assert!(spans.iter().any(|span| span.name == "server"));
+        assert!(spans.iter().all(|span| span.name != "discarded"));
+    });
+}
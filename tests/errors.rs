@@ -0,0 +1,57 @@
+use std::error::Error;
+use std::fmt;
+
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+/// An error with an optional source, to build cause chains.
+#[derive(Debug)]
+struct Failure(&'static str, Option<Box<Failure>>);
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl Error for Failure {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.1.as_deref().map(|source| source as _)
+    }
+}
+
+#[test]
+fn error_causes_are_indexed() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_indexed_error_causes(2));
+
+    let error = Failure(
+        "request failed",
+        Some(Box::new(Failure(
+            "connection reset",
+            Some(Box::new(Failure("broken pipe", None))),
+        ))),
+    );
+    tracing::subscriber::with_default(subscriber, || {
+        drop(tracing::info_span!("request", error = &error as &dyn Error));
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let property = |key: &str| {
+        spans[0]
+            .properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_ref())
+    };
+    assert_eq!(property("error"), Some("request failed"));
+    assert_eq!(property("error.cause.0"), Some("connection reset"));
+    assert_eq!(property("error.cause.1"), Some("broken pipe"));
+    assert_eq!(property("error.cause.2"), None);
+    assert_eq!(property("error.chain"), None);
+}
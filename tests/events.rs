@@ -0,0 +1,38 @@
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn events_without_fields_carry_their_metadata() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default().with(FastraceCompatLayer::new());
+
+    let line = tracing::subscriber::with_default(subscriber, || {
+        let _request = tracing::info_span!("request").entered();
+        tracing::warn!("cache miss");
+        line!() - 1
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let event = &spans[0].events[0];
+    let property = |key: &str| {
+        event
+            .properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_ref())
+    };
+    assert_eq!(event.name, "cache miss");
+    assert_eq!(property("level"), Some("WARN"));
+    assert_eq!(property("target"), Some(module_path!()));
+    if cfg!(feature = "strip-location") {
+        assert_eq!(property("code.namespace"), None);
+        assert_eq!(property("code.lineno"), None);
+    } else {
+        assert_eq!(property("code.namespace"), Some(module_path!()));
+        assert_eq!(property("code.lineno"), Some(line.to_string().as_str()));
+    }
+}
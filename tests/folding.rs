@@ -0,0 +1,42 @@
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::EventFolding;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn low_severity_events_are_folded_into_properties() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_event_folding(Level::INFO, EventFolding::Properties));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let _request = tracing::info_span!("request").entered();
+        tracing::debug!("connecting");
+        tracing::debug!("connected");
+        tracing::trace!("polled");
+        tracing::info!("request sent");
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let request = &spans[0];
+    let property = |key: &str| {
+        request
+            .properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_ref())
+    };
+    assert_eq!(property("log.debug.0"), Some("connecting"));
+    assert_eq!(property("log.debug.1"), Some("connected"));
+    assert_eq!(property("log.trace.0"), Some("polled"));
+    let events: Vec<_> = request
+        .events
+        .iter()
+        .map(|event| event.name.as_ref())
+        .collect();
+    assert_eq!(events, ["request sent"]);
+}
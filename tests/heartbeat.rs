@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn heartbeats_are_reported_while_the_span_is_open() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_heartbeats(Duration::from_millis(10)));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let request = tracing::info_span!("request").entered();
+        tracing::info!("started");
+        std::thread::sleep(Duration::from_millis(20));
+        tracing::info!("progress");
+        fastrace::flush();
+
+        let spans = spans.lock();
+        let names: Vec<_> = spans.iter().map(|span| span.name.as_ref()).collect();
+        assert_eq!(names, ["span.heartbeat"]);
+        let property = |key: &str| {
+            spans[0]
+                .properties
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.to_string())
+        };
+        assert_eq!(property("span.name").as_deref(), Some("request"));
+        assert_eq!(property("span.event_count").as_deref(), Some("1"));
+        drop(spans);
+        drop(request);
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let request = spans.iter().find(|span| span.name == "request").unwrap();
+    let heartbeat = spans
+        .iter()
+        .find(|span| span.name == "span.heartbeat")
+        .unwrap();
+    assert_eq!(heartbeat.trace_id, request.trace_id);
+    assert_eq!(heartbeat.parent_id, request.span_id);
+}
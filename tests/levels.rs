@@ -0,0 +1,42 @@
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn target_levels_filter_spans_and_events() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default().with(
+        FastraceCompatLayer::new().with_target_levels([
+            ("sqlx", Level::DEBUG),
+            ("sqlx::pool", Level::WARN),
+            ("", Level::INFO),
+        ]),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        let _request = tracing::info_span!(target: "app", "request").entered();
+        let _verbose = tracing::debug_span!(target: "app", "verbose").entered();
+        let _query = tracing::debug_span!(target: "sqlx::query", "query").entered();
+        tracing::debug!(target: "sqlx::query", "statement");
+        tracing::info!(target: "sqlx::pool", "acquired");
+        tracing::warn!(target: "sqlx::pool", "slow acquire");
+        tracing::debug!(target: "app", "details");
+    });
+    fastrace::flush();
+
+    let mut spans = spans.lock().clone();
+    spans.sort_by_key(|span| span.begin_time_unix_ns);
+    let names: Vec<_> = spans.iter().map(|span| span.name.as_ref()).collect();
+    assert_eq!(names, ["request", "query"]);
+    // The span nested in the filtered span is re-parented to the nearest recorded ancestor.
+    assert_eq!(spans[1].parent_id, spans[0].span_id);
+    let events: Vec<_> = spans[1]
+        .events
+        .iter()
+        .map(|event| event.name.as_ref())
+        .collect();
+    assert_eq!(events, ["statement", "slow acquire"]);
+}
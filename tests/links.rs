@@ -0,0 +1,47 @@
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn links_are_recorded_as_properties() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default().with(FastraceCompatLayer::new());
+
+    tracing::subscriber::with_default(subscriber, || {
+        drop(tracing::info_span!(
+            "batch",
+            links = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01, \
+                     00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        ));
+        drop(tracing::info_span!("invalid", links = "not a traceparent"));
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let properties = |name: &str| {
+        let span = spans.iter().find(|span| span.name == name).unwrap();
+        span.properties
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .filter(|(key, _)| key.starts_with("link"))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(properties("batch"), [
+        (
+            "link.0.trace_id".to_string(),
+            "0af7651916cd43dd8448eb211c80319c".to_string()
+        ),
+        ("link.0.span_id".to_string(), "b7ad6b7169203331".to_string()),
+        (
+            "link.1.trace_id".to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736".to_string()
+        ),
+        ("link.1.span_id".to_string(), "00f067aa0ba902b7".to_string()),
+    ]);
+    assert_eq!(properties("invalid"), [(
+        "links".to_string(),
+        "not a traceparent".to_string()
+    )]);
+}
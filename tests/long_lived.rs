@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn long_lived_spans_are_reported_once() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default().with(
+        FastraceCompatLayer::new()
+            .with_long_lived_span_detection(Duration::from_millis(20))
+            .with_span_timeout(Duration::from_millis(60)),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        let leaked = tracing::info_span!("leaked");
+        drop(tracing::info_span!("quick"));
+        for _ in 0..10 {
+            std::thread::sleep(Duration::from_millis(15));
+            // Deadlines are checked when spans or events are created.
+            tracing::info!("tick");
+        }
+        fastrace::flush();
+
+        let spans = spans.lock();
+        let reports: Vec<_> = spans
+            .iter()
+            .filter(|span| span.name == "long_lived_span")
+            .collect();
+        assert_eq!(reports.len(), 1);
+        assert!(
+            reports[0]
+                .properties
+                .iter()
+                .any(|(key, value)| key == "span.name" && value == "leaked")
+        );
+        let leaked_span = spans.iter().find(|span| span.name == "leaked").unwrap();
+        assert_eq!(reports[0].trace_id, leaked_span.trace_id);
+        assert!(
+            leaked_span
+                .properties
+                .iter()
+                .any(|(key, value)| key == "timed_out" && value == "true")
+        );
+        drop(spans);
+        drop(leaked);
+    });
+}
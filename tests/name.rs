@@ -0,0 +1,43 @@
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn fastrace_name_overrides_the_span_name() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default().with(FastraceCompatLayer::new());
+
+    tracing::subscriber::with_default(subscriber, || {
+        let request = tracing::info_span!(
+            "request",
+            fastrace.name = "GET /users/{id}",
+            route = tracing::field::Empty
+        );
+        let _request = request.enter();
+        request.record("route", "/users/{id}");
+        let handler = tracing::info_span!("handler", fastrace.name = tracing::field::Empty);
+        handler.record("fastrace.name", "late");
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let request = spans
+        .iter()
+        .find(|span| span.name == "GET /users/{id}")
+        .unwrap();
+    assert!(
+        request
+            .properties
+            .iter()
+            .all(|(key, _)| key != "fastrace.name")
+    );
+    let handler = spans.iter().find(|span| span.name == "handler").unwrap();
+    assert!(
+        handler
+            .properties
+            .iter()
+            .any(|(key, value)| key == "fastrace.name" && value == "late")
+    );
+}
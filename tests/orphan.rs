@@ -0,0 +1,46 @@
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace::prelude::*;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn orphans_join_the_first_local_parent() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_orphan_buffer(16));
+
+    let root_context = SpanContext::random();
+    tracing::subscriber::with_default(subscriber, || {
+        drop(tracing::info_span!("init"));
+        let server = tracing::info_span!("server");
+        server.in_scope(|| drop(tracing::info_span!("bind")));
+
+        {
+            let root = Span::root("request", root_context);
+            let _guard = root.set_local_parent();
+            // Exiting a span is enough for the buffer to find the local parent.
+            server.in_scope(|| drop(tracing::info_span!("accept")));
+        }
+        drop(server);
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let find = |name: &str| {
+        spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap_or_else(|| panic!("{name} was not reported"))
+    };
+    let request = find("request");
+    let server = find("server");
+    for name in ["init", "server", "bind", "accept"] {
+        assert_eq!(find(name).trace_id, root_context.trace_id, "{name}");
+    }
+    assert_eq!(find("init").parent_id, request.span_id);
+    assert_eq!(server.parent_id, request.span_id);
+    assert_eq!(find("bind").parent_id, server.span_id);
+    assert_eq!(find("accept").parent_id, server.span_id);
+}
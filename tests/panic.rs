@@ -0,0 +1,52 @@
+use std::fmt;
+use std::panic::AssertUnwindSafe;
+use std::panic::catch_unwind;
+
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Panics when formatted, to panic while the layer updates a span.
+struct Panicking;
+
+impl fmt::Debug for Panicking {
+    fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+        panic!("formatting panicked")
+    }
+}
+
+#[test]
+fn panics_are_recorded_on_the_current_span() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    fastrace_tracing::install_panic_hook();
+    let subscriber = tracing_subscriber::Registry::default().with(FastraceCompatLayer::new());
+
+    tracing::subscriber::with_default(subscriber, || {
+        let _request = tracing::info_span!("request").entered();
+        assert!(catch_unwind(|| panic!("request failed")).is_err());
+
+        // This would deadlock if the hook accessed the span while its extensions are locked.
+        let poisoned = tracing::info_span!(parent: None, "poisoned", flag = tracing::field::Empty);
+        poisoned.in_scope(|| {
+            let record = || poisoned.record("flag", tracing::field::debug(Panicking));
+            assert!(catch_unwind(AssertUnwindSafe(record)).is_err());
+        });
+        // The extensions of the span are poisoned, so it cannot be closed. Its trace is never
+        // reported.
+        std::mem::forget(poisoned);
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let events: Vec<_> = spans
+        .iter()
+        .find(|span| span.name == "request")
+        .unwrap()
+        .events
+        .iter()
+        .map(|event| event.name.as_ref())
+        .collect();
+    assert_eq!(events, ["request failed"]);
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use fastrace_tracing::SpanAction;
+use tracing::Level;
+use tracing::span;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::layer::Layer;
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Records the names of the spans it sees, standing in for another layer of the subscriber.
+struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+impl<S: tracing::Subscriber> Layer<S> for SpanNames {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, _: &span::Id, _: Context<'_, S>) {
+        self.0.lock().unwrap().push(attrs.metadata().name());
+    }
+}
+
+#[test]
+fn policy_decides_span_actions() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let layer = FastraceCompatLayer::new()
+        .with_span_level_threshold(Level::INFO)
+        .with_span_policy(|meta| match meta.name() {
+            "discarded" => SpanAction::Discard,
+            "demoted" => SpanAction::EventOnly,
+            _ => SpanAction::Record,
+        });
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(layer)
+        .with(SpanNames(seen.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let _request = tracing::info_span!("request").entered();
+        {
+            let _discarded = tracing::info_span!("discarded").entered();
+            let _child = tracing::info_span!("child").entered();
+        }
+        drop(tracing::info_span!("demoted"));
+        drop(tracing::debug_span!("verbose"));
+    });
+    fastrace::flush();
+
+    // The other layers of the subscriber still see the spans that are not bridged.
+    assert_eq!(*seen.lock().unwrap(), [
+        "request",
+        "discarded",
+        "child",
+        "demoted",
+        "verbose"
+    ]);
+
+    let mut spans = spans.lock().clone();
+    spans.sort_by_key(|span| span.begin_time_unix_ns);
+    let names: Vec<_> = spans.iter().map(|span| span.name.as_ref()).collect();
+    assert_eq!(names, ["request", "child"]);
+    assert_eq!(spans[1].parent_id, spans[0].span_id);
+    let events: Vec<_> = spans[0]
+        .events
+        .iter()
+        .map(|event| event.name.as_ref())
+        .collect();
+    assert_eq!(events, ["demoted", "verbose"]);
+}
@@ -0,0 +1,39 @@
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn event_properties_are_limited() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default().with(
+        FastraceCompatLayer::new()
+            .with_location(false)
+            .with_max_properties_per_event(4),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        let _request = tracing::info_span!("request").entered();
+        tracing::info!(a = 1, b = 2, c = 3, d = 4, "large event");
+        tracing::info!(a = 1, "small event");
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let properties = |name: &str| {
+        spans[0]
+            .events
+            .iter()
+            .find(|event| event.name == name)
+            .unwrap()
+            .properties
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+    };
+    let large = properties("large event");
+    assert_eq!(large.len(), 5);
+    assert!(large.contains(&("properties.dropped".to_string(), "2".to_string())));
+    assert_eq!(properties("small event").len(), 3);
+}
@@ -0,0 +1,30 @@
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn reporter_targets_are_not_bridged() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_reporter_targets(["hyper"]));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let _request = tracing::info_span!("request").entered();
+        let _export = tracing::info_span!(target: "hyper::client", "export").entered();
+        tracing::info!(target: "hyper::proto", "flushed");
+        tracing::info!(target: "hyperx", "unrelated");
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let names: Vec<_> = spans.iter().map(|span| span.name.as_ref()).collect();
+    assert_eq!(names, ["request"]);
+    let events: Vec<_> = spans[0]
+        .events
+        .iter()
+        .map(|event| event.name.as_ref())
+        .collect();
+    assert_eq!(events, ["unrelated"]);
+}
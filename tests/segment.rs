@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn long_spans_are_split_into_segments() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_span_segmentation(3, Duration::from_secs(3600)));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let _request = tracing::info_span!("request").entered();
+        let _consumer = tracing::info_span!("consumer", queue = "orders").entered();
+        for i in 0..7 {
+            tracing::info!(i, "message");
+        }
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let request = spans.iter().find(|span| span.name == "request").unwrap();
+    let property = |span: &fastrace::collector::SpanRecord, key: &str| {
+        span.properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.to_string())
+    };
+    let mut segments: Vec<_> = spans
+        .iter()
+        .filter(|span| span.name == "consumer")
+        .collect();
+    segments.sort_by_key(|span| property(span, "segment.index"));
+
+    assert_eq!(segments.len(), 3);
+    let events: usize = segments.iter().map(|segment| segment.events.len()).sum();
+    assert_eq!(events, 7);
+    for (index, segment) in segments.iter().enumerate() {
+        assert_eq!(segment.trace_id, request.trace_id);
+        assert_eq!(segment.parent_id, request.span_id);
+        assert_eq!(property(segment, "queue").as_deref(), Some("orders"));
+        assert!(segment.events.len() <= 3);
+        if index > 0 {
+            let previous = segments[index - 1].span_id.0;
+            assert_eq!(
+                property(segment, "segment.previous_span_id"),
+                Some(format!("{previous:016x}"))
+            );
+        }
+    }
+}
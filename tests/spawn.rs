@@ -0,0 +1,41 @@
+#![cfg(feature = "tokio")]
+
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn origin_is_recorded_on_the_first_bridged_span() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default().with(FastraceCompatLayer::new());
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    tracing::subscriber::with_default(subscriber, || {
+        runtime.block_on(async {
+            let _request = tracing::info_span!("request").entered();
+            let handle = fastrace_tracing::spawn(async {
+                drop(tracing::info_span!("health check", fastrace.skip = true));
+                drop(tracing::info_span!("job"));
+                drop(tracing::info_span!("cleanup"));
+            });
+            handle.await.unwrap();
+        });
+    });
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let find = |name: &str| spans.iter().find(|span| span.name == name).unwrap();
+    let has_origin = |name: &str| {
+        find(name)
+            .properties
+            .iter()
+            .any(|(key, _)| key == "spawned_from.span_id")
+    };
+    assert!(spans.iter().all(|span| span.name != "health check"));
+    assert!(has_origin("job"));
+    assert!(!has_origin("cleanup"));
+}
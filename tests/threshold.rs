@@ -0,0 +1,37 @@
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn spans_below_the_threshold_are_recorded_as_events() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_span_level_threshold(Level::INFO));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let _request = tracing::info_span!("request").entered();
+        let _lookup = tracing::debug_span!("cache lookup", key = "user:42").entered();
+        tracing::info!("cache miss");
+        let _fetch = tracing::info_span!("fetch").entered();
+    });
+    fastrace::flush();
+
+    let mut spans = spans.lock().clone();
+    spans.sort_by_key(|span| span.begin_time_unix_ns);
+    let names: Vec<_> = spans.iter().map(|span| span.name.as_ref()).collect();
+    assert_eq!(names, ["request", "fetch"]);
+    assert_eq!(spans[1].parent_id, spans[0].span_id);
+
+    let events = &spans[0].events;
+    let names: Vec<_> = events.iter().map(|event| event.name.as_ref()).collect();
+    assert_eq!(names, ["cache lookup", "cache miss"]);
+    assert!(
+        events[0]
+            .properties
+            .iter()
+            .any(|(key, value)| key == "key" && value == "user:42")
+    );
+}
@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use fastrace::collector::Config;
+use fastrace::collector::TestReporter;
+use fastrace_tracing::FastraceCompatLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn timed_out_spans_are_reported() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(FastraceCompatLayer::new().with_span_timeout(Duration::from_millis(50)));
+
+    tracing::subscriber::with_default(subscriber, || {
+        drop(tracing::info_span!("quick"));
+        let stuck = tracing::info_span!("stuck");
+        std::thread::sleep(Duration::from_millis(100));
+
+        // Timeouts are checked when spans are created.
+        drop(tracing::info_span!("trigger"));
+        fastrace::flush();
+        let spans = spans.lock();
+        let timed_out = |name: &str| {
+            spans
+                .iter()
+                .find(|span| span.name == name)
+                .unwrap_or_else(|| panic!("{name} was not reported"))
+                .properties
+                .iter()
+                .any(|(key, value)| key == "timed_out" && value == "true")
+        };
+        assert!(timed_out("stuck"));
+        assert!(!timed_out("quick"));
+        assert!(!timed_out("trigger"));
+        drop(stuck);
+    });
+}